@@ -4,25 +4,213 @@
 
 use std::{
     borrow::Cow,
+    collections::{HashMap, VecDeque},
     error::Error,
     fmt::Display,
     io::{BufRead, BufReader},
-    path::Path,
-    process::{Child, Command, Stdio},
-    time::Duration,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    pin::Pin,
+    process::{Child, Command, ExitStatus, Stdio},
+    str::FromStr,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_util::{
+    Stream, StreamExt,
+    future::{Either, select, try_join, try_join_all},
+};
+use reqwest::{
+    Client, ClientBuilder, Request, RequestBuilder, Response, StatusCode,
+    header::{ACCEPT, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue},
+};
+use serde::{
+    Deserialize, Deserializer, Serialize,
+    de::{self, SeqAccess, Visitor},
+    ser::{SerializeMap, SerializeSeq},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::{OwnedSemaphorePermit, Semaphore},
+    task::JoinHandle,
 };
 
-use reqwest::{Client, ClientBuilder};
-use serde::{Deserialize, Serialize, ser::SerializeSeq};
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const DEFAULT_READY_MARKER: &str = "launcher: using default app name & version";
+const OUTPUT_CAPACITY: usize = 1000;
+const CRASH_OUTPUT_LINES: usize = 20;
+const DEFAULT_FAILURE_PATTERNS: &[&str] = &["Traceback", "error:", "Cannot load app"];
+const STARTUP_FAILURE_GRACE: Duration = Duration::from_secs(1);
+const PROBE_MIN_TIMEOUT: Duration = Duration::from_millis(1);
+/// Largest response data length of extended APDUs.
+const MAX_APDU_RESPONSE_LEN: usize = 65536;
+const DEFAULT_AUTOMATION_VERSION: u32 = 1;
+const APP_NAME_ENV: &str = "SPECULOS_APPNAME";
+const ROW_TOLERANCE: u32 = 2;
+const HOLD_TO_CONFIRM_DURATION: Duration = Duration::from_secs(2);
 
 /// Speculos client.
 ///
-/// The Speculos process owned by [`SpeculosClient`] will be terminated upon dropping.
+/// The Speculos process owned by [`SpeculosClient`] will be terminated upon dropping, unless the
+/// client is converted with [`into_detached`](SpeculosClient::into_detached).
+///
+/// Speculos is always launched with a headless display, and all input is sent through its API
+/// rather than simulated keystrokes, so scripted input never depends on window focus or keyboard
+/// layout. Input remapping options like `--keymap` only apply to interactive displays and are thus
+/// not exposed.
 #[derive(Debug)]
 pub struct SpeculosClient {
-    process: Child,
+    process: Mutex<Option<Child>>,
+    kill_on_drop: bool,
     port: u16,
+    apdu_port: u16,
     client: Client,
+    apdu_timeout: Duration,
+    automation_timeout: Duration,
+    launcher_info: Option<LauncherInfo>,
+    metrics: Option<Mutex<HashMap<String, EndpointMetrics>>>,
+    unresponsive: Arc<AtomicBool>,
+    watchdog: Option<JoinHandle<()>>,
+    automation_persistent: bool,
+    last_automation: Mutex<Option<Vec<u8>>>,
+    apdu_count: AtomicU64,
+    apdu_faults: Mutex<HashMap<u64, ApduFault>>,
+    output: Arc<Mutex<VecDeque<String>>>,
+    apdu_connection: tokio::sync::Mutex<Option<TcpStream>>,
+    screenshot_step: AtomicU64,
+    config: SpeculosClientBuilder,
+}
+
+/// Builder for launching a [`SpeculosClient`] with custom options.
+///
+/// Obtain one with [`SpeculosClient::builder`].
+#[derive(Clone)]
+pub struct SpeculosClientBuilder {
+    model: DeviceModel,
+    port: u16,
+    apdu_port: u16,
+    app: PathBuf,
+    host: String,
+    launch_timeout: Duration,
+    apdu_timeout: Duration,
+    automation_timeout: Duration,
+    automation_version: u32,
+    apdu_field: String,
+    post_ready_delay: Duration,
+    poll_interval: Duration,
+    #[cfg(feature = "compression")]
+    compression: bool,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    tcp_keepalive: Option<Duration>,
+    headers: HeaderMap,
+    seed: Option<String>,
+    sdk: Option<String>,
+    api_level: Option<u32>,
+    detect_api_level: bool,
+    ready_marker: String,
+    failure_patterns: Vec<String>,
+    metrics: bool,
+    on_request: Option<RequestHook>,
+    on_response: Option<ResponseHook>,
+    auto_screenshot: Option<PathBuf>,
+    dry_run: Option<Vec<u8>>,
+    detached: bool,
+    trace: bool,
+    progressive: bool,
+    app_name: Option<String>,
+    extra_args: Vec<String>,
+    python: Option<PathBuf>,
+    invoke_as_module: bool,
+    executable: Option<PathBuf>,
+    #[cfg(feature = "docker")]
+    docker_image: Option<String>,
+}
+
+/// A pool of pre-launched [`SpeculosClient`] instances, handed out as [`PooledClient`] guards.
+///
+/// Launching Speculos is slow. A pool pays that cost once upfront, after which tests check out
+/// ready instances with [`get`](Self::get).
+#[derive(Debug, Clone)]
+pub struct SpeculosPool {
+    inner: Arc<PoolInner>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    idle: Mutex<VecDeque<SpeculosClient>>,
+    available: Arc<Semaphore>,
+    /// Instances not terminated yet, whether idle or checked out.
+    live: AtomicUsize,
+    poll_interval: Duration,
+}
+
+/// A [`SpeculosClient`] checked out from a [`SpeculosPool`], returned to the pool on drop.
+///
+/// Dereferences to the underlying [`SpeculosClient`]. On drop, the instance is reset in the
+/// background before being made available again. The reset reboots Speculos, which clears the app
+/// state and automation rules. State set up during the checkout is discarded as well: injected
+/// APDU faults, the watchdog, metrics, screenshot step numbering, and any poll interval set with
+/// [`SpeculosClient::set_poll_interval`]. Instances failing to reset, or dropped outside of a Tokio
+/// runtime, are terminated instead, permanently shrinking the pool.
+#[derive(Debug)]
+pub struct PooledClient {
+    client: Option<SpeculosClient>,
+    permit: Option<OwnedSemaphorePermit>,
+    pool: Arc<PoolInner>,
+}
+
+type RequestHook = Arc<dyn Fn(&RequestInfo<'_>) + Send + Sync>;
+type ResponseHook = Arc<dyn Fn(&ResponseInfo<'_>) + Send + Sync>;
+
+/// An API request about to be sent, passed to [`SpeculosClientBuilder::on_request`] hooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestInfo<'a> {
+    /// The HTTP method.
+    pub method: &'a str,
+    /// The endpoint path.
+    pub path: &'a str,
+    /// The request body, if any.
+    pub body: Option<&'a [u8]>,
+}
+
+/// An API response just received, passed to [`SpeculosClientBuilder::on_response`] hooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseInfo<'a> {
+    /// The HTTP method of the request.
+    pub method: &'a str,
+    /// The endpoint path of the request.
+    pub path: &'a str,
+    /// The HTTP status code.
+    pub status: u16,
+    /// Time taken until the response headers were received.
+    pub elapsed: Duration,
+}
+
+/// Identity of the app launched by Speculos.
+///
+/// Speculos takes the app name and version from the `SPECULOS_APPNAME` environment variable, set
+/// with [`SpeculosClientBuilder::app_name`], or otherwise from the `ledger.app_name` and
+/// `ledger.app_version` sections embedded in the app ELF by recent Ledger SDKs. The same sources
+/// are used here, unless the launcher line itself ends with a `name:version` pair, as printed by
+/// some Speculos versions. Apps without any of these run under a default name and version, in
+/// which case both fields are `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LauncherInfo {
+    /// The launcher line signaling readiness, if it was detected from the process output.
+    pub line: Option<String>,
+    /// Name of the app, if known.
+    pub app_name: Option<String>,
+    /// Version of the app, if known.
+    pub app_version: Option<String>,
 }
 
 /// Ledger device model.
@@ -43,24 +231,31 @@ pub enum DeviceModel {
 }
 
 /// Speculos automation rule.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+///
+/// Rules deserialize from the same JSON format Speculos accepts, which allows loading them from
+/// fixture files. Serializing then deserializing a rule yields an equal rule, except that an
+/// [`AutomationAction::BothButtons`] action comes back as its two [`AutomationAction::Button`]
+/// actions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AutomationRule<'a> {
     /// Exact text match.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub text: Option<Cow<'a, str>>,
     /// Regex text match.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub regexp: Option<Cow<'a, str>>,
     /// X coordinate match.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub x: Option<u32>,
     /// Y coordinate match.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub y: Option<u32>,
     /// Conditions for this rule to be activated.
-    pub conditions: &'a [AutomationCondition<'a>],
+    #[serde(default)]
+    pub conditions: Cow<'a, [AutomationCondition<'a>]>,
     /// Actions to perform when this rule is applied.
-    pub actions: &'a [AutomationAction<'a>],
+    #[serde(serialize_with = "serialize_actions")]
+    pub actions: Cow<'a, [AutomationAction<'a>]>,
 }
 
 /// Speculos automation actions.
@@ -73,6 +268,15 @@ pub enum AutomationAction<'a> {
         /// The pressed status to change to.
         pressed: bool,
     },
+    /// Press or release both buttons.
+    ///
+    /// Speculos has no native action for this, so it's sent as a [`Button`](Self::Button) action
+    /// for each button, left first. It thus fails to serialize on its own, outside of an
+    /// [`AutomationRule`].
+    BothButtons {
+        /// The pressed status to change to.
+        pressed: bool,
+    },
     /// Touch or release the screen.
     Finger {
         /// The X coordinate whose touched status is to be updated.
@@ -111,6 +315,236 @@ pub enum Button {
     Right,
 }
 
+/// A text element displayed on screen, as reported by Speculos.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ScreenEvent {
+    /// The displayed text.
+    pub text: String,
+    /// X coordinate of the text.
+    pub x: u32,
+    /// Y coordinate of the text.
+    pub y: u32,
+}
+
+/// Stream of [`ScreenEvent`]s as they are displayed, created with
+/// [`SpeculosClient::event_stream`].
+///
+/// The underlying HTTP connection is closed as soon as the stream is dropped, so it's safe to stop
+/// polling it at any point, e.g. from a losing `tokio::select!` branch.
+pub struct EventStream {
+    inner: Pin<Box<dyn Stream<Item = Result<ScreenEvent, SpeculosError>> + Send>>,
+}
+
+/// A single user input on the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    /// Press and release a button.
+    Button(Button),
+    /// Press and release both buttons.
+    BothButtons,
+    /// Touch and release the screen.
+    Tap {
+        /// The X coordinate to touch.
+        x: u32,
+        /// The Y coordinate to touch.
+        y: u32,
+    },
+    /// Touch the screen, hold, then release.
+    LongPress {
+        /// The X coordinate to touch.
+        x: u32,
+        /// The Y coordinate to touch.
+        y: u32,
+        /// How long to hold before releasing.
+        duration: Duration,
+    },
+}
+
+/// A touchscreen state change, used for scripting gestures with
+/// [`SpeculosClient::finger_sequence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FingerEvent {
+    /// The X coordinate whose touched status is to be updated.
+    pub x: u32,
+    /// The Y coordinate whose touched status is to be updated.
+    pub y: u32,
+    /// The touched status to change to.
+    pub touched: bool,
+    /// Time to wait after this event before performing the next one.
+    pub delay: Duration,
+}
+
+/// A simulated APDU failure, injected with [`SpeculosClient::inject_apdu_fault`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApduFault {
+    /// Respond with the status word and no data, without forwarding the APDU to Speculos.
+    StatusWord(u16),
+    /// Fail with a connection reset IO error, without forwarding the APDU to Speculos.
+    Disconnect,
+}
+
+/// A rectangular area of the screen, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    /// X coordinate of the top-left corner.
+    pub x: u32,
+    /// Y coordinate of the top-left corner.
+    pub y: u32,
+    /// Width of the area.
+    pub width: u32,
+    /// Height of the area.
+    pub height: u32,
+}
+
+/// Common touchscreen buttons, located with [`DeviceModel::button_region`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamedButton {
+    /// The confirm button at the bottom right of review screens.
+    Confirm,
+    /// The reject button at the bottom left of review screens.
+    Reject,
+    /// The back button at the top left.
+    Back,
+    /// The settings button at the top right of the app home screen.
+    Settings,
+}
+
+/// Navigation for approving an operation pending on the device.
+///
+/// Used with [`SpeculosClient::sign_with_approval`]. Once `review_text` is displayed, `next` is
+/// performed until `confirm_text` is displayed, at which point `confirm` is performed. After each
+/// `next`, the screen is waited to change and settle before checking for `confirm_text` again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalFlow<'a> {
+    /// Text displayed when the review starts.
+    pub review_text: Cow<'a, str>,
+    /// Text displayed on the screen where the operation can be confirmed.
+    pub confirm_text: Cow<'a, str>,
+    /// Gesture for moving to the next review screen.
+    pub next: Gesture,
+    /// Gesture for confirming the operation.
+    pub confirm: Gesture,
+}
+
+/// A screen captured for UI baselines, as part of a [`GoldenSequence`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoldenScreen {
+    /// The text elements displayed, in the order reported by Speculos.
+    pub texts: Vec<String>,
+    /// PNG-encoded screenshot, serialized as hex. Only compared when present.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_screenshot",
+        deserialize_with = "deserialize_screenshot"
+    )]
+    pub screenshot: Option<Vec<u8>>,
+}
+
+/// A named sequence of distinct screens, used as a UI baseline with
+/// [`SpeculosClient::assert_screen_sequence`].
+///
+/// Sequences can be recorded with [`SpeculosClient::record_screens`] or built screen by screen
+/// with [`SpeculosClient::capture_screen`], then serialized to disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoldenSequence {
+    /// Name of the sequence.
+    pub name: String,
+    /// The screens, in order of display.
+    pub screens: Vec<GoldenScreen>,
+}
+
+/// Latency statistics of requests to a single API endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndpointMetrics {
+    /// Number of requests made.
+    pub count: u64,
+    /// Shortest request latency.
+    pub min: Duration,
+    /// Longest request latency.
+    pub max: Duration,
+    /// Sum of all request latencies.
+    pub total: Duration,
+}
+
+/// An APDU response split into its data and status word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApduResponse {
+    /// Response data, excluding the status word.
+    pub data: Vec<u8>,
+    /// The status word.
+    pub sw: u16,
+}
+
+/// An APDU status word.
+///
+/// Status words display as hex, along with their name if commonly used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StatusWord(pub u16);
+
+/// Category of a [`StatusWord`], following the ISO 7816-4 ranges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusWordCategory {
+    /// Normal processing: `9000` and `61XX`.
+    Success,
+    /// Processed with a warning: `62XX` and `63XX`.
+    Warning,
+    /// Any other status word.
+    Error,
+}
+
+/// An ECDSA signature over secp256k1, parsed from an APDU response with [`Signature::parse`].
+#[cfg(feature = "signature")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signature {
+    /// The `r` value, big-endian.
+    pub r: [u8; 32],
+    /// The `s` value, big-endian.
+    pub s: [u8; 32],
+    /// The recovery ID or `v` value as returned by the app, if present in the layout.
+    pub v: Option<u8>,
+}
+
+/// Layout of a signature in an APDU response, used with [`Signature::parse`].
+#[cfg(feature = "signature")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignatureLayout {
+    /// 65 bytes of `v || r || s`, as returned by the Ledger Ethereum app.
+    Vrs,
+    /// 65 bytes of `r || s || v`.
+    Rsv,
+    /// 64 bytes of `r || s`, without `v`.
+    Rs,
+    /// A DER-encoded `SEQUENCE` of `r` and `s`, as returned by Bitcoin-style apps, without `v`.
+    ///
+    /// Nothing may follow the sequence, so any trailing sighash type must be removed first.
+    Der,
+}
+
+/// Expectations on an APDU response, checked all at once with [`check`](Self::check).
+///
+/// Expectations are chained, e.g. `ApduMatcher::new().sw(0x9000).data_len(65)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApduMatcher {
+    sw: Option<u16>,
+    data: Option<Vec<u8>>,
+    data_len: Option<usize>,
+    data_prefix: Option<Vec<u8>>,
+}
+
+/// Error parsing a [`DeviceModel`] from its slug.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDeviceModelError(String);
+
+/// Reasons for an [`AutomationRule`] to be considered invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutomationRuleError {
+    /// Both `text` and `regexp` are set, which can never match at the same time.
+    TextAndRegexp,
+    /// None of `text`, `regexp`, `x` and `y` is set, leaving the rule without a matcher.
+    NoMatcher,
+}
+
 /// Speculos client errors.
 #[derive(Debug)]
 pub enum SpeculosError {
@@ -118,31 +552,274 @@ pub enum SpeculosError {
     IoError(std::io::Error),
     /// HTTP errors from `reqwest.
     ReqwestError(reqwest::Error),
+    /// An automation rule failed validation.
+    InvalidAutomationRule(AutomationRuleError),
+    /// The operation did not complete within the allowed time.
+    Timeout,
+    /// The Speculos process exited unexpectedly.
+    ProcessExited(ExitStatus),
+    /// The Speculos process was terminated by a crash signal, such as `SIGSEGV` or `SIGABRT`.
+    ///
+    /// Detected on launch and when an API request fails after the crash, on Unix only. Other exits
+    /// are reported as [`ProcessExited`](Self::ProcessExited).
+    AppCrashed {
+        /// The signal number.
+        signal: i32,
+        /// The last 20 lines of process output, which is only captured when launched with
+        /// [`SpeculosClientBuilder::launch`].
+        stderr: String,
+    },
+    /// Speculos printed a line matching a [failure
+    /// pattern](SpeculosClientBuilder::failure_pattern) while starting.
+    StartupFailed {
+        /// Up to 20 lines of process output, starting from the first line matching a failure
+        /// pattern.
+        output: String,
+    },
+    /// The app to be launched does not exist.
+    AppNotFound(PathBuf),
+    /// The app path is empty or consists of whitespace only.
+    EmptyAppPath,
+    /// The app path exists but is not a regular file, e.g. a directory.
+    AppNotAFile(PathBuf),
+    /// The APDU TCP server is not enabled.
+    ApduPortDisabled,
+    /// The launch options are invalid.
+    InvalidLaunchOption(String),
+    /// A required environment variable is not set.
+    MissingEnvVar(&'static str),
+    /// An environment variable has an invalid value.
+    InvalidEnvVar {
+        /// Name of the environment variable.
+        name: &'static str,
+        /// The invalid value.
+        value: String,
+    },
+    /// JSON errors from `serde_json`.
+    JsonError(serde_json::Error),
+    /// Hex decoding errors from `hex`.
+    HexError(hex::FromHexError),
+    /// Image decoding errors from `image`.
+    #[cfg(feature = "image")]
+    ImageError(image::ImageError),
+    /// APDU answer parsing errors from `coins-ledger`.
+    #[cfg(feature = "coins-ledger")]
+    LedgerError(coins_ledger::LedgerError),
+    /// No QR code was detected on screen.
+    #[cfg(feature = "qr")]
+    QrCodeNotFound,
+    /// QR code decoding errors from `rqrr`.
+    #[cfg(feature = "qr")]
+    QrError(rqrr::DeQRError),
+    /// The Speculos process stopped responding, as detected by the watchdog.
+    Unresponsive,
+    /// A [`SpeculosPool`] has no instance left to check out.
+    PoolExhausted,
+    /// The `speculos` command could not be found.
+    SpeculosNotFound {
+        /// The paths tried, in order.
+        tried: Vec<PathBuf>,
+    },
+    /// An APDU response is too short to contain a status word.
+    ApduResponseTooShort(usize),
+    /// An APDU response has a status word other than the expected one.
+    UnexpectedStatusWord {
+        /// The expected status word.
+        expected: u16,
+        /// The actual status word.
+        actual: u16,
+    },
+    /// A screen differs from the one in a [`GoldenSequence`].
+    ScreenMismatch {
+        /// Index of the screen in the sequence.
+        index: usize,
+        /// The expected text elements.
+        expected: Vec<String>,
+        /// The text elements displayed. These equal `expected` when only the screenshot differs.
+        actual: Vec<String>,
+    },
+    /// The screen text differs from the expected one.
+    ScreenTextMismatch {
+        /// The expected text.
+        expected: String,
+        /// The text displayed.
+        actual: String,
+    },
+    /// The connection to the APDU TCP server was lost and could not be re-established.
+    ApduReconnectFailed(std::io::Error),
+    /// An APDU response does not satisfy an [`ApduMatcher`].
+    ApduMismatch {
+        /// Description of the first unsatisfied expectation.
+        reason: String,
+        /// The full response, including the status word.
+        response: Vec<u8>,
+    },
+    /// A BIP32 derivation path could not be parsed.
+    #[cfg(feature = "coins-ledger")]
+    InvalidDerivationPath(String),
+    /// A get-address APDU response does not follow the expected layout.
+    #[cfg(feature = "coins-ledger")]
+    MalformedAddressResponse(Vec<u8>),
+    /// An address does not match the expected one.
+    #[cfg(feature = "coins-ledger")]
+    AddressMismatch {
+        /// The expected address.
+        expected: String,
+        /// The actual address.
+        actual: String,
+    },
+    /// Touchscreen coordinates fall outside of the screen.
+    TouchOutOfBounds {
+        /// The x coordinate.
+        x: u32,
+        /// The y coordinate.
+        y: u32,
+        /// The screen width.
+        width: u32,
+        /// The screen height.
+        height: u32,
+    },
+    /// A text element that must not be displayed was displayed.
+    ForbiddenText {
+        /// The displayed text element.
+        text: String,
+    },
+    /// A port to be used by Speculos is already in use, e.g. by another client.
+    PortInUse(u16),
+    /// Speculos refused the automation rules.
+    AutomationRejected {
+        /// The reason given by Speculos.
+        reason: String,
+    },
+    /// A numeric button code is neither `1` nor `2`.
+    InvalidButtonCode(u8),
+    /// A screenshot is not a valid PNG image.
+    MalformedScreenshot,
+    /// Speculos emulates a model other than the expected one.
+    ModelMismatch {
+        /// The expected model.
+        expected: DeviceModel,
+        /// The actual screen width.
+        width: u32,
+        /// The actual screen height.
+        height: u32,
+    },
+    /// A capture interval is zero.
+    #[cfg(feature = "gif")]
+    ZeroInterval,
+    /// Response data does not follow the expected signature layout.
+    #[cfg(feature = "signature")]
+    MalformedSignature {
+        /// The expected layout.
+        layout: SignatureLayout,
+        /// The response data.
+        data: Vec<u8>,
+    },
 }
 
-#[derive(Serialize)]
 struct PostApduRequest<'a> {
-    #[serde(with = "hex")]
+    field: &'a str,
     data: &'a [u8],
 }
 
 #[derive(Deserialize)]
-struct PostApduResponse {
-    #[serde(with = "hex")]
-    data: Vec<u8>,
+struct PostApduResponse<'a> {
+    #[serde(borrow)]
+    data: Cow<'a, str>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
 }
 
 #[derive(Serialize)]
-struct PostAutomationRequest<'a> {
+struct PostAutomationRequest<'a, 'b> {
     version: u32,
-    rules: &'a [AutomationRule<'a>],
+    rules: &'a [AutomationRule<'b>],
+}
+
+#[derive(Deserialize)]
+struct GetEventsResponse {
+    events: Vec<ScreenEvent>,
+}
+
+#[derive(Serialize)]
+struct PostButtonRequest {
+    action: &'static str,
+}
+
+#[derive(Serialize)]
+struct PostFingerRequest {
+    action: &'static str,
+    x: u32,
+    y: u32,
 }
 
 impl SpeculosClient {
+    /// Creates a [`SpeculosClientBuilder`] for launching Speculos with custom options.
+    ///
+    /// Use different `port` values when launching multiple instances to avoid port conflicts.
+    pub fn builder<P: AsRef<Path>>(model: DeviceModel, port: u16, app: P) -> SpeculosClientBuilder {
+        SpeculosClientBuilder {
+            model,
+            port,
+            apdu_port: 0,
+            app: app.as_ref().to_path_buf(),
+            host: "127.0.0.1".to_owned(),
+            launch_timeout: DEFAULT_TIMEOUT,
+            apdu_timeout: DEFAULT_TIMEOUT,
+            automation_timeout: DEFAULT_TIMEOUT,
+            automation_version: DEFAULT_AUTOMATION_VERSION,
+            apdu_field: "data".to_owned(),
+            post_ready_delay: Duration::ZERO,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            #[cfg(feature = "compression")]
+            compression: false,
+            pool_idle_timeout: None,
+            pool_max_idle_per_host: None,
+            tcp_keepalive: None,
+            headers: HeaderMap::new(),
+            seed: None,
+            sdk: None,
+            api_level: None,
+            detect_api_level: false,
+            ready_marker: DEFAULT_READY_MARKER.to_owned(),
+            failure_patterns: DEFAULT_FAILURE_PATTERNS
+                .iter()
+                .map(|pattern| (*pattern).to_owned())
+                .collect(),
+            metrics: false,
+            on_request: None,
+            on_response: None,
+            auto_screenshot: None,
+            dry_run: None,
+            detached: false,
+            trace: false,
+            progressive: false,
+            app_name: None,
+            extra_args: Vec::new(),
+            python: None,
+            invoke_as_module: false,
+            executable: None,
+            #[cfg(feature = "docker")]
+            docker_image: None,
+        }
+    }
+
+    /// Creates a new [`SpeculosClient`] by launching the `speculos` command as configured by
+    /// environment variables.
+    ///
+    /// See [`SpeculosClientBuilder::from_env`] for the supported variables.
+    pub fn from_env() -> Result<Self, SpeculosError> {
+        SpeculosClientBuilder::from_env()?.launch()
+    }
+
     /// Creates a new [`SpeculosClient`] by launching the `speculos` command with a default timeout
     /// of 10 seconds.
     ///
-    /// This method requires the `speculos` command to be available from `PATH`.
+    /// The `speculos` command is looked up from `PATH`, then from common install locations. Use
+    /// [`SpeculosClientBuilder::executable`] to launch it from elsewhere.
     ///
     /// Use different `port` values when launching multiple instances to avoid port conflicts.
     pub fn new<P: AsRef<Path>>(
@@ -150,12 +827,16 @@ impl SpeculosClient {
         port: u16,
         app: P,
     ) -> Result<Self, SpeculosError> {
-        Self::new_with_timeout(model, port, app, Duration::from_secs(10))
+        Self::builder(model, port, app).launch()
     }
 
     /// Creates a new [`SpeculosClient`] by launching the `speculos` command with a custom timeout.
     ///
-    /// This method requires the `speculos` command to be available from `PATH`.
+    /// The timeout applies to both launching and subsequent API requests. Use
+    /// [`builder`](Self::builder) to set them separately.
+    ///
+    /// The `speculos` command is looked up from `PATH`, then from common install locations. Use
+    /// [`SpeculosClientBuilder::executable`] to launch it from elsewhere.
     ///
     /// Use different `port` values when launching multiple instances to avoid port conflicts.
     pub fn new_with_timeout<P: AsRef<Path>>(
@@ -164,36 +845,114 @@ impl SpeculosClient {
         app: P,
         timeout: Duration,
     ) -> Result<Self, SpeculosError> {
-        let mut process = Command::new("speculos")
-            .args([
-                "--api-port",
-                &port.to_string(),
-                "--apdu-port",
-                "0",
-                "-m",
-                model.slug(),
-                "--display",
-                "headless",
-                &app.as_ref().display().to_string(),
-            ])
-            .stderr(Stdio::piped())
-            .spawn()?;
-
-        // Wait for process to be ready by monitoring stderr
-        if let Some(stderr) = process.stderr.take() {
-            let reader = BufReader::new(stderr);
-            for line in reader.lines().map_while(Result::ok) {
-                if line.contains("launcher: using default app name & version") {
-                    break;
-                }
-            }
-        }
+        Self::builder(model, port, app)
+            .launch_timeout(timeout)
+            .apdu_timeout(timeout)
+            .automation_timeout(timeout)
+            .launch()
+    }
 
-        Ok(Self {
-            process,
-            port,
-            client: ClientBuilder::new().timeout(timeout).build().unwrap(),
-        })
+    /// Creates a new [`SpeculosClient`] emulating a Ledger Nano S.
+    ///
+    /// This is a shorthand for [`new`](Self::new) with [`DeviceModel::Nanos`].
+    pub fn nanos<P: AsRef<Path>>(port: u16, app: P) -> Result<Self, SpeculosError> {
+        Self::new(DeviceModel::Nanos, port, app)
+    }
+
+    /// Creates a new [`SpeculosClient`] emulating a Ledger Nano X.
+    ///
+    /// This is a shorthand for [`new`](Self::new) with [`DeviceModel::Nanox`].
+    pub fn nanox<P: AsRef<Path>>(port: u16, app: P) -> Result<Self, SpeculosError> {
+        Self::new(DeviceModel::Nanox, port, app)
+    }
+
+    /// Creates a new [`SpeculosClient`] emulating a Ledger Nano S Plus.
+    ///
+    /// This is a shorthand for [`new`](Self::new) with [`DeviceModel::Nanosp`].
+    pub fn nanosp<P: AsRef<Path>>(port: u16, app: P) -> Result<Self, SpeculosError> {
+        Self::new(DeviceModel::Nanosp, port, app)
+    }
+
+    /// Creates a new [`SpeculosClient`] emulating a Ledger Blue.
+    ///
+    /// This is a shorthand for [`new`](Self::new) with [`DeviceModel::Blue`].
+    pub fn blue<P: AsRef<Path>>(port: u16, app: P) -> Result<Self, SpeculosError> {
+        Self::new(DeviceModel::Blue, port, app)
+    }
+
+    /// Creates a new [`SpeculosClient`] emulating a Ledger Stax.
+    ///
+    /// This is a shorthand for [`new`](Self::new) with [`DeviceModel::Stax`].
+    pub fn stax<P: AsRef<Path>>(port: u16, app: P) -> Result<Self, SpeculosError> {
+        Self::new(DeviceModel::Stax, port, app)
+    }
+
+    /// Creates a new [`SpeculosClient`] emulating a Ledger Flex.
+    ///
+    /// This is a shorthand for [`new`](Self::new) with [`DeviceModel::Flex`].
+    pub fn flex<P: AsRef<Path>>(port: u16, app: P) -> Result<Self, SpeculosError> {
+        Self::new(DeviceModel::Flex, port, app)
+    }
+
+    /// Creates a new [`SpeculosClient`] by launching the `speculos` command with a default timeout
+    /// of 10 seconds, without blocking the current thread.
+    ///
+    /// Unlike [`new`](Self::new), readiness is detected by polling the API, which makes it safe to
+    /// launch multiple instances concurrently from async code.
+    ///
+    /// The `speculos` command is looked up from `PATH`, then from common install locations. Use
+    /// [`SpeculosClientBuilder::executable`] to launch it from elsewhere.
+    ///
+    /// Use different `port` values when launching multiple instances to avoid port conflicts.
+    pub async fn launch_async<P: AsRef<Path>>(
+        model: DeviceModel,
+        port: u16,
+        app: P,
+    ) -> Result<Self, SpeculosError> {
+        Self::builder(model, port, app).launch_async().await
+    }
+
+    /// Creates a new [`SpeculosClient`] by launching the `speculos` command with a custom timeout,
+    /// without blocking the current thread.
+    ///
+    /// The timeout applies to both readiness polling and subsequent API requests. Use
+    /// [`builder`](Self::builder) to set them separately.
+    ///
+    /// The `speculos` command is looked up from `PATH`, then from common install locations. Use
+    /// [`SpeculosClientBuilder::executable`] to launch it from elsewhere.
+    ///
+    /// Use different `port` values when launching multiple instances to avoid port conflicts.
+    pub async fn launch_async_with_timeout<P: AsRef<Path>>(
+        model: DeviceModel,
+        port: u16,
+        app: P,
+        timeout: Duration,
+    ) -> Result<Self, SpeculosError> {
+        Self::builder(model, port, app)
+            .launch_timeout(timeout)
+            .apdu_timeout(timeout)
+            .automation_timeout(timeout)
+            .launch_async()
+            .await
+    }
+
+    /// Launches Speculos as configured by `builder`, runs `f` with the client, then terminates the
+    /// process and waits for it to exit.
+    ///
+    /// Unlike relying on [`Drop`], teardown is complete once this returns, whether `f` succeeds or
+    /// fails. Errors from `f` take precedence over those from teardown. Should `f` panic, the
+    /// process is still terminated on drop, but not waited for.
+    pub async fn with<F, T>(builder: SpeculosClientBuilder, f: F) -> Result<T, SpeculosError>
+    where
+        F: AsyncFnOnce(&mut SpeculosClient) -> Result<T, SpeculosError>,
+    {
+        let mut client = builder.launch_async().await?;
+        let result = f(&mut client).await;
+        let shutdown = client.shutdown();
+
+        let output = result?;
+        shutdown?;
+        Ok(output)
     }
 
     /// Sends an APDU command via the API.
@@ -201,64 +960,3924 @@ impl SpeculosClient {
     /// This method accepts and returns raw bytes. The caller should handle parsing.
     ///
     /// A common choice is to use `APDUCommand` and `APDUAnswer` types from the `coins-ledger`
-    /// crate.
+    /// crate, which [`apdu_command`](Self::apdu_command) does directly when the `coins-ledger`
+    /// feature is enabled.
+    ///
+    /// APDUs are exchanged whole. Speculos does not expose its emulated USB HID transport, so
+    /// behaviors specific to 64-byte HID frame boundaries cannot be reproduced via this method.
+    ///
+    /// This method never panics, regardless of what the server returns. Error statuses and
+    /// malformed responses (non-UTF-8, truncated JSON, invalid hex) are all reported as errors,
+    /// making it suitable for use in fuzzing harnesses.
+    ///
+    /// On the wire, the APDU is sent to `POST /apdu` as `{"data": "<hex>"}`, and the response is
+    /// read from `{"data": "<hex>"}`, status word included. The request field can be renamed with
+    /// [`SpeculosClientBuilder::apdu_field`] for Speculos forks expecting another one.
     pub async fn apdu(&self, data: &[u8]) -> Result<Vec<u8>, SpeculosError> {
-        let response = self
-            .client
-            .post(format!("http://localhost:{}/apdu", self.port))
-            .json(&PostApduRequest { data })
-            .send()
-            .await?;
-        let body = response.json::<PostApduResponse>().await.unwrap();
+        let mut buffer = Vec::new();
+        self.apdu_into(data, &mut buffer).await?;
 
-        Ok(body.data)
+        Ok(buffer)
     }
 
-    /// Sends an automation request via the API.
-    pub async fn automation(&self, rules: &[AutomationRule<'_>]) -> Result<(), SpeculosError> {
-        let response = self
+    /// Sends a hex-encoded APDU command via the API, returning the hex-encoded response.
+    ///
+    /// This is convenient for APDUs taken from documentation or test vectors. Whitespace in `hex`
+    /// is ignored, and invalid hex results in [`SpeculosError::HexError`]. The response includes
+    /// the status word and is lowercase.
+    pub async fn apdu_hex(&self, hex: &str) -> Result<String, SpeculosError> {
+        let data = hex::decode(hex.split_whitespace().collect::<String>())?;
+
+        Ok(hex::encode(self.apdu(&data).await?))
+    }
+
+    /// Sends an APDU command via the API, writing the response into a caller-provided buffer.
+    ///
+    /// The buffer is cleared before writing. Reusing the same buffer across calls avoids
+    /// allocating a new one for each response, which adds up when sending many APDUs.
+    ///
+    /// Like [`apdu`](Self::apdu), this method never panics regardless of what the server returns.
+    /// The buffer content is unspecified when an error is returned.
+    pub async fn apdu_into(&self, data: &[u8], buffer: &mut Vec<u8>) -> Result<(), SpeculosError> {
+        if let Some(fault) = self.next_apdu_fault() {
+            buffer.clear();
+            buffer.extend_from_slice(&fault.response()?);
+            return Ok(());
+        }
+
+        let request = self
             .client
-            .post(format!("http://localhost:{}/automation", self.port))
-            .json(&PostAutomationRequest { version: 1, rules })
-            .send()
+            .post(self.url("apdu"))
+            .timeout(self.apdu_timeout)
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT, "application/json")
+            .json(&PostApduRequest {
+                field: &self.config.apdu_field,
+                data,
+            });
+        let response = self.send(request).await?;
+        let body = response.error_for_status()?.bytes().await?;
+        let body = serde_json::from_slice::<PostApduResponse<'_>>(&body)?;
+
+        buffer.clear();
+        buffer.resize(body.data.len() / 2, 0);
+        hex::decode_to_slice(body.data.as_ref(), buffer)?;
+
+        Ok(())
+    }
+
+    /// Sends a `coins-ledger` APDU command via the API.
+    ///
+    /// Non-success status words are not treated as errors. Check the returned answer with
+    /// `APDUAnswer::is_success` or `APDUAnswer::retcode`.
+    #[cfg(feature = "coins-ledger")]
+    pub async fn apdu_command(
+        &self,
+        command: &coins_ledger::APDUCommand,
+    ) -> Result<coins_ledger::APDUAnswer, SpeculosError> {
+        let response = self.apdu(&command.serialize()).await?;
+
+        Ok(coins_ledger::APDUAnswer::from_answer(response)?)
+    }
+
+    /// Gets the address at a BIP32 derivation path such as `m/44'/60'/0'/0/0`, using the common
+    /// get-address APDU convention.
+    ///
+    /// The command is sent with the given `cla` and `ins`, e.g. `0xe0` and `0x02` for Ethereum or
+    /// `0xe0` and `0x40` for the legacy Bitcoin app, with P1 and P2 set to `0` to skip on-screen
+    /// confirmation. The data is the number of path components followed by each component as a
+    /// big-endian `u32`. The response is expected to hold the length-prefixed public key followed
+    /// by the length-prefixed address in ASCII, which is returned as is.
+    #[cfg(feature = "coins-ledger")]
+    pub async fn get_address(&self, cla: u8, ins: u8, path: &str) -> Result<String, SpeculosError> {
+        let path = parse_derivation_path(path)?;
+        let mut data = Vec::with_capacity(1 + 4 * path.len());
+        data.push(path.len() as u8);
+        for component in path {
+            data.extend_from_slice(&component.to_be_bytes());
+        }
+
+        let answer = self
+            .apdu_command(&coins_ledger::APDUCommand {
+                cla,
+                ins,
+                p1: 0,
+                p2: 0,
+                data: data.into(),
+                response_len: None,
+            })
             .await?;
+        if !answer.is_success() {
+            return Err(SpeculosError::UnexpectedStatusWord {
+                expected: StatusWord::OK.0,
+                actual: answer.retcode(),
+            });
+        }
+
+        let data = answer.data().unwrap_or_default();
+        let address = data
+            .first()
+            .and_then(|pubkey_len| data.get(1 + *pubkey_len as usize..))
+            .and_then(|rest| rest.get(1..1 + *rest.first()? as usize))
+            .and_then(|address| String::from_utf8(address.to_vec()).ok())
+            .ok_or_else(|| SpeculosError::MalformedAddressResponse(data.to_vec()))?;
+
+        Ok(address)
+    }
+
+    /// Checks that the address at a BIP32 derivation path is `expected`.
+    ///
+    /// See [`get_address`](Self::get_address) for the APDU convention used. Addresses are compared
+    /// exactly, so `expected` must use the same format as the app, e.g. regarding a `0x` prefix or
+    /// letter case. A mismatch results in [`SpeculosError::AddressMismatch`], which points out the
+    /// first differing character.
+    #[cfg(feature = "coins-ledger")]
+    pub async fn assert_address(
+        &self,
+        cla: u8,
+        ins: u8,
+        path: &str,
+        expected: &str,
+    ) -> Result<(), SpeculosError> {
+        let actual = self.get_address(cla, ins, path).await?;
+        if actual != expected {
+            return Err(SpeculosError::AddressMismatch {
+                expected: expected.to_owned(),
+                actual,
+            });
+        }
 
-        response.error_for_status()?;
         Ok(())
     }
-}
 
-impl Drop for SpeculosClient {
-    fn drop(&mut self) {
-        let _ = self.process.kill();
+    /// Sends an APDU command via the API, returning the response data and status word separately.
+    ///
+    /// Responses shorter than the 2-byte status word result in
+    /// [`SpeculosError::ApduResponseTooShort`].
+    pub async fn apdu_split(&self, data: &[u8]) -> Result<(Vec<u8>, u16), SpeculosError> {
+        let response = ApduResponse::try_from(self.apdu(data).await?)?;
+
+        Ok((response.data, response.sw))
     }
-}
 
-impl DeviceModel {
-    /// Gets the model slug to be used on Speculos.
-    pub const fn slug(&self) -> &'static str {
-        match self {
-            Self::Nanos => "nanos",
-            Self::Nanox => "nanox",
-            Self::Nanosp => "nanosp",
-            Self::Blue => "blue",
-            Self::Stax => "stax",
-            Self::Flex => "flex",
+    /// Sends an APDU command via the API, checking that the response status word is `expected`.
+    ///
+    /// Returns the response data without the status word. A mismatch results in
+    /// [`SpeculosError::UnexpectedStatusWord`], which names common status words, making
+    /// negative-path tests read like `client.assert_sw(&apdu, 0x6985)`.
+    pub async fn assert_sw(&self, data: &[u8], expected: u16) -> Result<Vec<u8>, SpeculosError> {
+        ApduResponse::try_from(self.apdu(data).await?)?.expect_sw(expected)
+    }
+
+    /// Sends an APDU command via the API, checking that the response status word indicates
+    /// success.
+    ///
+    /// Status words categorized as [`StatusWordCategory::Warning`] are accepted only when
+    /// `accept_warnings` is `true`. Others result in [`SpeculosError::UnexpectedStatusWord`].
+    pub async fn apdu_checked(
+        &self,
+        data: &[u8],
+        accept_warnings: bool,
+    ) -> Result<ApduResponse, SpeculosError> {
+        let response = ApduResponse::try_from(self.apdu(data).await?)?;
+
+        match response.status_word().category() {
+            StatusWordCategory::Success => Ok(response),
+            StatusWordCategory::Warning if accept_warnings => Ok(response),
+            _ => Err(SpeculosError::UnexpectedStatusWord {
+                expected: StatusWord::OK.0,
+                actual: response.sw,
+            }),
         }
     }
-}
 
-impl<'a> Serialize for AutomationCondition<'a> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    /// Sends a sequence of APDU commands via the API, yielding each response as it arrives.
+    ///
+    /// Requests are pulled from `requests` lazily, one at a time, and only after the previous
+    /// response has been received. This allows the iterator to build later requests from the
+    /// acknowledgements of earlier ones, e.g. when streaming a large payload in chunks. The stream
+    /// ends after the first error.
+    pub fn apdu_stream<I>(&self, requests: I) -> impl Stream<Item = Result<Vec<u8>, SpeculosError>>
     where
-        S: serde::Serializer,
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
     {
-        let mut seq = serializer.serialize_seq(Some(2))?;
-        seq.serialize_element(&self.varname)?;
-        seq.serialize_element(&self.value)?;
-        seq.end()
+        futures_util::stream::unfold(Some(requests.into_iter()), async |requests| {
+            let mut requests = requests?;
+            let request = requests.next()?;
+
+            let response = self.apdu(request.as_ref()).await;
+            let requests = response.is_ok().then_some(requests);
+
+            Some((response, requests))
+        })
     }
-}
+
+    /// Sends a sequence of APDU commands via the API, calling `on_progress` with the number of
+    /// commands sent so far and the total after each response.
+    ///
+    /// This suits protocols uploading large payloads in chunks, where only the final response
+    /// matters. Each response but the last must indicate success as with
+    /// [`apdu_checked`](Self::apdu_checked), and the remaining chunks are not sent otherwise.
+    /// The last response is returned as-is, in the same format as [`apdu`](Self::apdu), or empty
+    /// if there are no chunks.
+    pub async fn apdu_chunked<I, F>(
+        &self,
+        chunks: I,
+        mut on_progress: F,
+    ) -> Result<Vec<u8>, SpeculosError>
+    where
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        I::Item: AsRef<[u8]>,
+        F: FnMut(usize, usize),
+    {
+        let chunks = chunks.into_iter();
+        let total = chunks.len();
+
+        let mut response = Vec::new();
+        for (ind, chunk) in chunks.enumerate() {
+            if ind + 1 < total {
+                self.apdu_checked(chunk.as_ref(), false).await?;
+            } else {
+                response = self.apdu(chunk.as_ref()).await?;
+            }
+            on_progress(ind + 1, total);
+        }
+
+        Ok(response)
+    }
+
+    /// Sends an APDU command via the TCP APDU server.
+    ///
+    /// The response has the same format as [`apdu`](Self::apdu). This requires the APDU port to be
+    /// enabled with [`SpeculosClientBuilder::apdu_port`].
+    ///
+    /// The connection is kept open across calls, and APDUs are sent one at a time. Should a kept
+    /// connection turn out to be closed before the APDU is sent, it's sent over a new connection
+    /// instead, or [`SpeculosError::ApduReconnectFailed`] is returned if one cannot be
+    /// established. Failures after the APDU is sent are never retried, as the APDU may already have
+    /// been processed. Any failure, including a timeout, closes the connection so that the next
+    /// call starts afresh.
+    pub async fn apdu_tcp(&self, data: &[u8]) -> Result<Vec<u8>, SpeculosError> {
+        let apdu_port = self.apdu_port().ok_or(SpeculosError::ApduPortDisabled)?;
+        if self.is_unresponsive() {
+            return Err(SpeculosError::Unresponsive);
+        }
+        if let Some(fault) = self.next_apdu_fault() {
+            return Ok(fault.response()?.to_vec());
+        }
+
+        let mut connection = self.apdu_connection.lock().await;
+        let response = tokio::time::timeout(self.apdu_timeout, async {
+            let stream = match connection.take() {
+                Some(mut stream) if !is_closed(&stream) => {
+                    match write_tcp(&mut stream, data).await {
+                        Ok(()) => stream,
+                        // A request that didn't go through can't have been processed
+                        Err(error) if is_disconnect(&error) => {
+                            self.reconnect_tcp(apdu_port, data).await?
+                        }
+                        Err(error) => return Err(error.into()),
+                    }
+                }
+                // Speculos may have closed the connection since it was last used
+                Some(_) => self.reconnect_tcp(apdu_port, data).await?,
+                None => {
+                    let mut stream =
+                        TcpStream::connect((self.config.host.as_str(), apdu_port)).await?;
+                    write_tcp(&mut stream, data).await?;
+                    stream
+                }
+            };
+
+            let stream = connection.insert(stream);
+            Ok(read_tcp(stream).await?)
+        })
+        .await
+        .map_err(|_| SpeculosError::Timeout)
+        .flatten();
+
+        // The connection is left in an unknown state mid-exchange
+        if response.is_err() {
+            *connection = None;
+        }
+
+        response
+    }
+
+    async fn reconnect_tcp(&self, apdu_port: u16, data: &[u8]) -> Result<TcpStream, SpeculosError> {
+        let mut stream = TcpStream::connect((self.config.host.as_str(), apdu_port))
+            .await
+            .map_err(SpeculosError::ApduReconnectFailed)?;
+        write_tcp(&mut stream, data).await?;
+
+        Ok(stream)
+    }
+
+    /// Makes the `nth` APDU from now on fail as specified by `fault`, for testing how callers
+    /// handle failures.
+    ///
+    /// `nth` starts at 1 for the next APDU, and `0` is treated as `1`. APDUs sent via both
+    /// [`apdu`](Self::apdu) and [`apdu_tcp`](Self::apdu_tcp) are counted. Multiple faults can be
+    /// pending at once.
+    pub fn inject_apdu_fault(&self, nth: u64, fault: ApduFault) {
+        let index = self.apdu_count.load(Ordering::Relaxed) + nth.max(1);
+        self.apdu_faults
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(index, fault);
+    }
+
+    /// Sends an automation request via the API.
+    ///
+    /// All rules are validated with [`AutomationRule::validate`] before being sent. Rules that pass
+    /// validation but are still refused by Speculos result in
+    /// [`SpeculosError::AutomationRejected`].
+    pub async fn automation(&self, rules: &[AutomationRule<'_>]) -> Result<(), SpeculosError> {
+        let body = serde_json::to_vec(&automation_request(rules, self.config.automation_version)?)?;
+        self.set_automation(body).await
+    }
+
+    /// Sends an arbitrary JSON body as an automation request via the API.
+    ///
+    /// This allows using automation features not yet supported by [`AutomationRule`]. The body is
+    /// sent as-is, including its `version` field, and isn't validated. Bodies refused by Speculos
+    /// result in [`SpeculosError::AutomationRejected`].
+    pub async fn automation_raw(&self, json: serde_json::Value) -> Result<(), SpeculosError> {
+        self.set_automation(serde_json::to_vec(&json)?).await
+    }
+
+    async fn set_automation(&self, body: Vec<u8>) -> Result<(), SpeculosError> {
+        self.post_automation(body.clone()).await?;
+
+        *self
+            .last_automation
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(body);
+        Ok(())
+    }
+
+    /// Sets whether the last automation rules sent with [`automation`](Self::automation) or
+    /// [`automation_raw`](Self::automation_raw) are re-applied after a [`reboot`](Self::reboot).
+    /// Defaults to `false`.
+    ///
+    /// Speculos loses all automation rules when relaunched, which otherwise silently breaks any
+    /// navigation relying on them.
+    pub fn set_automation_persistent(&mut self, persistent: bool) {
+        self.automation_persistent = persistent;
+    }
+
+    /// Sets the interval between polls of helpers waiting on Speculos, replacing the one set with
+    /// [`SpeculosClientBuilder::poll_interval`].
+    ///
+    /// Individual calls can use a different interval with the `*_with_interval` variants of the
+    /// helpers, such as [`navigate_with_interval`](Self::navigate_with_interval).
+    pub fn set_poll_interval(&mut self, interval: Duration) {
+        self.config.poll_interval = interval;
+    }
+
+    /// Reboots the emulated device by relaunching Speculos with the same options, waiting for it to
+    /// become ready again.
+    ///
+    /// Unlike restarting the app, this restores the full initial device state. Readiness is
+    /// detected by polling the API, so the [`line`](LauncherInfo::line) of
+    /// [`launcher_info`](Self::launcher_info) is cleared.
+    /// Automation rules are lost unless persisted with
+    /// [`set_automation_persistent`](Self::set_automation_persistent).
+    pub async fn reboot(&mut self) -> Result<(), SpeculosError> {
+        let Some(process) = self
+            .process
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+        else {
+            // Nothing to reboot in dry-run mode or when attached
+            return Ok(());
+        };
+        self.config.kill_process(process)?;
+        process.wait()?;
+
+        *process = self.config.spawn_process(Stdio::null)?;
+        self.launcher_info = Some(self.config.launcher_info(None));
+        *self.apdu_connection.get_mut() = None;
+        self.wait_for_api(self.config.launch_timeout).await?;
+        tokio::time::sleep(self.config.post_ready_delay).await;
+
+        // A fresh process gets a clean slate
+        self.unresponsive.store(false, Ordering::Relaxed);
+
+        if self.automation_persistent {
+            let body = self
+                .last_automation
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone();
+            if let Some(body) = body {
+                self.post_automation(body).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn shutdown(&mut self) -> Result<(), SpeculosError> {
+        let process = self
+            .process
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(child) = process {
+            self.config.kill_process(child)?;
+            child.wait()?;
+        }
+
+        *process = None;
+        Ok(())
+    }
+
+    /// Waits for the Speculos process to exit on its own, e.g. after an
+    /// [`AutomationAction::Exit`] action, for at most `timeout`.
+    ///
+    /// Returns the exit status of the process, or [`SpeculosError::Timeout`] if it's still
+    /// running. There is no process to wait for in dry-run mode, which always times out.
+    pub async fn wait_for_exit(&mut self, timeout: Duration) -> Result<ExitStatus, SpeculosError> {
+        self.wait_for_exit_with_interval(timeout, self.config.poll_interval)
+            .await
+    }
+
+    /// Like [`wait_for_exit`](Self::wait_for_exit), but polling every `interval` instead of the
+    /// client's [poll interval](Self::set_poll_interval).
+    pub async fn wait_for_exit_with_interval(
+        &mut self,
+        timeout: Duration,
+        interval: Duration,
+    ) -> Result<ExitStatus, SpeculosError> {
+        let Some(process) = self
+            .process
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+        else {
+            return Err(SpeculosError::Timeout);
+        };
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(status) = process.try_wait()? {
+                return Ok(status);
+            }
+
+            if Instant::now() >= deadline {
+                return Err(SpeculosError::Timeout);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Suspends the Speculos process until [`resume`](Self::resume) is called.
+    ///
+    /// Speculos offers no pause mechanism, so the process is stopped with `SIGSTOP` instead, or
+    /// with `docker pause` when running in Docker. While paused, API requests and APDUs are not
+    /// answered and eventually time out, and a running watchdog may mark the client unresponsive.
+    /// The emulated app itself keeps running, but cannot make progress without Speculos relaying
+    /// its I/O.
+    ///
+    /// This is a no-op in dry-run mode.
+    #[cfg(unix)]
+    pub fn pause(&self) -> Result<(), SpeculosError> {
+        self.signal_process(true)
+    }
+
+    /// Resumes a Speculos process suspended by [`pause`](Self::pause).
+    ///
+    /// This is a no-op in dry-run mode.
+    #[cfg(unix)]
+    pub fn resume(&self) -> Result<(), SpeculosError> {
+        self.signal_process(false)
+    }
+
+    #[cfg(unix)]
+    fn signal_process(&self, pause: bool) -> Result<(), SpeculosError> {
+        let Some(pid) = self.pid() else {
+            return Ok(());
+        };
+
+        let mut command = Command::new("kill");
+        command
+            .arg(if pause { "-STOP" } else { "-CONT" })
+            .arg(pid.to_string());
+
+        // Stopping the `docker` client would leave the container running
+        #[cfg(feature = "docker")]
+        if self.config.docker_image.is_some() {
+            command = Command::new("docker");
+            command
+                .arg(if pause { "pause" } else { "unpause" })
+                .arg(self.config.container_name());
+        }
+
+        let status = command
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        if !status.success() {
+            return Err(std::io::Error::other(format!(
+                "failed to {} speculos: {}",
+                if pause { "pause" } else { "resume" },
+                status
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Starts a background task that pings the API every `interval`, marking the client as
+    /// unresponsive once `max_failures` consecutive pings fail.
+    ///
+    /// Once marked, all API calls fail immediately with [`SpeculosError::Unresponsive`] instead of
+    /// each running into its own timeout. The mark is cleared by a successful
+    /// [`reboot`](Self::reboot). Calling this again replaces the previous watchdog, which is also
+    /// stopped when the client is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn start_watchdog(&mut self, interval: Duration, max_failures: u32) {
+        if let Some(watchdog) = self.watchdog.take() {
+            watchdog.abort();
+        }
+
+        // Nothing to watch in dry-run mode
+        if self.config.dry_run.is_some() {
+            return;
+        }
+
+        let client = self.client.clone();
+        let url = self.url("events?currentscreenonly=true");
+        let unresponsive = self.unresponsive.clone();
+
+        self.watchdog = Some(tokio::spawn(async move {
+            let mut failures = 0;
+            loop {
+                tokio::time::sleep(interval).await;
+
+                let response = client.get(&url).timeout(interval).send().await;
+                if response.is_ok_and(|response| response.status().is_success()) {
+                    failures = 0;
+                } else {
+                    failures += 1;
+                    if failures >= max_failures {
+                        unresponsive.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+        }));
+    }
+
+    /// Checks whether the watchdog has marked the client as unresponsive.
+    ///
+    /// Always returns `false` unless a watchdog was started with
+    /// [`start_watchdog`](Self::start_watchdog).
+    pub fn is_unresponsive(&self) -> bool {
+        self.unresponsive.load(Ordering::Relaxed)
+    }
+
+    /// Gets the latency statistics of API requests so far, keyed by endpoint path.
+    ///
+    /// Returns `None` unless enabled with [`SpeculosClientBuilder::metrics`].
+    pub fn metrics(&self) -> Option<HashMap<String, EndpointMetrics>> {
+        self.metrics.as_ref().map(|metrics| {
+            metrics
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone()
+        })
+    }
+
+    /// Gets the port of the Speculos HTTP API.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Gets the port of the Speculos APDU TCP server, if enabled.
+    pub fn apdu_port(&self) -> Option<u16> {
+        Some(self.apdu_port).filter(|port| *port != 0)
+    }
+
+    /// Gets the command line Speculos was launched with, quoted for POSIX shells.
+    ///
+    /// See [`SpeculosClientBuilder::launch_command_string`] for details.
+    pub fn launch_command_string(&self) -> Result<String, SpeculosError> {
+        self.config.launch_command_string()
+    }
+
+    /// Gets the process ID of the Speculos process, or `None` in dry-run mode.
+    ///
+    /// When running in Docker, this is the ID of the `docker` client process rather than of
+    /// Speculos inside the container.
+    pub fn pid(&self) -> Option<u32> {
+        self.process
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .map(Child::id)
+    }
+
+    /// Converts the client into one that does not terminate the Speculos process upon dropping.
+    ///
+    /// This is useful for sharing a single emulator instance across tests, e.g. via a lazily
+    /// initialized static. Note that the process then outlives the client unless it's terminated
+    /// by other means, such as an [`AutomationAction::Exit`] action.
+    ///
+    /// When the process output is captured, i.e. when launched with a
+    /// [ready marker](SpeculosClientBuilder::ready_marker), Speculos can no longer write its
+    /// output once the current process exits. To keep an instance running beyond the current
+    /// process, launch it with [`SpeculosClientBuilder::detached`] instead. Either way, other
+    /// clients can attach to the instance by port with [`SpeculosClientBuilder::connect`].
+    pub fn into_detached(mut self) -> Self {
+        self.kill_on_drop = false;
+        self
+    }
+
+    /// Gets the identity of the launched app.
+    ///
+    /// Returns `None` in dry-run mode and when attached with [`SpeculosClientBuilder::connect`],
+    /// as no app was launched.
+    pub fn launcher_info(&self) -> Option<&LauncherInfo> {
+        self.launcher_info.as_ref()
+    }
+
+    /// Gets the most recent lines of Speculos output, up to 1000 lines.
+    ///
+    /// Output is only captured when readiness was detected from the process output, i.e. when
+    /// launched with [`new`](Self::new) or [`SpeculosClientBuilder::launch`], and stops being
+    /// captured after a [`reboot`](Self::reboot). Combined with [`SpeculosClientBuilder::trace`],
+    /// this shows where an app stalls.
+    pub fn output(&self) -> Vec<String> {
+        self.output
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Captures the current screen as PNG-encoded bytes.
+    pub async fn screenshot(&self) -> Result<Vec<u8>, SpeculosError> {
+        let request = self
+            .client
+            .get(self.url("screenshot"))
+            .timeout(self.automation_timeout);
+        let response = self.send(request).await?;
+        let body = response.error_for_status()?.bytes().await?;
+
+        Ok(body.into())
+    }
+
+    /// Checks that Speculos emulates the `expected` model, e.g. to catch a stale instance left on
+    /// a reused port.
+    ///
+    /// As the API doesn't report the model, it's checked by comparing the resolution of a
+    /// [`screenshot`](Self::screenshot) against [`DeviceModel::screen_size`]. Models sharing a
+    /// resolution, i.e. Nano X and Nano S Plus, can't be told apart. A mismatch results in
+    /// [`SpeculosError::ModelMismatch`].
+    pub async fn assert_model(&self, expected: DeviceModel) -> Result<(), SpeculosError> {
+        let (width, height) =
+            png_size(&self.screenshot().await?).ok_or(SpeculosError::MalformedScreenshot)?;
+
+        if (width, height) != expected.screen_size() {
+            return Err(SpeculosError::ModelMismatch {
+                expected,
+                width,
+                height,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Captures the current screen as raw pixels.
+    ///
+    /// Returns the width, height, and RGBA bytes of the screen, row by row.
+    #[cfg(feature = "image")]
+    pub async fn framebuffer(&self) -> Result<(u32, u32, Vec<u8>), SpeculosError> {
+        let png = self.screenshot().await?;
+        let image =
+            image::load_from_memory_with_format(&png, image::ImageFormat::Png)?.into_rgba8();
+
+        Ok((image.width(), image.height(), image.into_raw()))
+    }
+
+    /// Captures an area of the current screen as PNG-encoded bytes.
+    ///
+    /// Speculos only captures the full screen, so the area is cropped client-side. Areas extending
+    /// past the screen edges are clamped to the screen bounds.
+    #[cfg(feature = "image")]
+    pub async fn screenshot_region(&self, rect: Rect) -> Result<Vec<u8>, SpeculosError> {
+        let png = self.screenshot().await?;
+        let image = image::load_from_memory_with_format(&png, image::ImageFormat::Png)?;
+
+        let mut cropped = Vec::new();
+        image
+            .crop_imm(rect.x, rect.y, rect.width, rect.height)
+            .write_to(
+                &mut std::io::Cursor::new(&mut cropped),
+                image::ImageFormat::Png,
+            )?;
+
+        Ok(cropped)
+    }
+
+    /// Decodes the QR code displayed on the current screen.
+    ///
+    /// When multiple QR codes are displayed, the first one detected is returned.
+    #[cfg(feature = "qr")]
+    pub async fn read_qr(&self) -> Result<String, SpeculosError> {
+        let png = self.screenshot().await?;
+        let image =
+            image::load_from_memory_with_format(&png, image::ImageFormat::Png)?.into_luma8();
+
+        let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+            image.width() as usize,
+            image.height() as usize,
+            |x, y| image.get_pixel(x as u32, y as u32).0[0],
+        );
+        let grid = prepared
+            .detect_grids()
+            .into_iter()
+            .next()
+            .ok_or(SpeculosError::QrCodeNotFound)?;
+        let (_, content) = grid.decode()?;
+
+        Ok(content)
+    }
+
+    /// Gets the text elements displayed on screen.
+    ///
+    /// When `current_screen_only` is `false`, all events since launch or the last
+    /// [`clear_events`](Self::clear_events) are returned, including those from screens no longer
+    /// displayed.
+    pub async fn events(
+        &self,
+        current_screen_only: bool,
+    ) -> Result<Vec<ScreenEvent>, SpeculosError> {
+        let request = self
+            .client
+            .get(self.url(&format!("events?currentscreenonly={}", current_screen_only)))
+            .timeout(self.automation_timeout)
+            .header(ACCEPT, "application/json");
+        let response = self.send(request).await?;
+        let body = response
+            .error_for_status()?
+            .json::<GetEventsResponse>()
+            .await?;
+
+        Ok(body.events)
+    }
+
+    /// Clears the events recorded by Speculos, setting a boundary for subsequent
+    /// [`events(false)`](Self::events) calls.
+    ///
+    /// Only events displayed after this call are returned from then on, which prevents matching
+    /// text from screens shown before the action under test.
+    pub async fn clear_events(&self) -> Result<(), SpeculosError> {
+        let request = self
+            .client
+            .delete(self.url("events"))
+            .timeout(self.automation_timeout);
+        let response = self.send(request).await?;
+
+        response.error_for_status()?;
+        Ok(())
+    }
+
+    /// Subscribes to text elements as they are displayed.
+    ///
+    /// The stream never ends on its own unless Speculos closes the connection. Use
+    /// [`event_stream_timeout`](Self::event_stream_timeout) to bound its lifetime.
+    pub async fn event_stream(&self) -> Result<EventStream, SpeculosError> {
+        self.event_stream_inner(None).await
+    }
+
+    /// Subscribes to text elements as they are displayed, for at most `timeout`.
+    ///
+    /// The stream ends once `timeout` elapses, releasing the underlying connection.
+    pub async fn event_stream_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<EventStream, SpeculosError> {
+        self.event_stream_inner(Some(tokio::time::Instant::now() + timeout))
+            .await
+    }
+
+    /// Waits until the text elements displayed satisfy `predicate`, for at most `timeout`.
+    ///
+    /// The predicate is first checked against the current screen, then again with every text
+    /// element subsequently displayed. Elements accumulate for the duration of the watch, including
+    /// those from screens no longer displayed, and the same element may appear more than once.
+    /// The event stream is closed as soon as the predicate is satisfied.
+    pub async fn watch_for<F>(&self, predicate: F, timeout: Duration) -> Result<(), SpeculosError>
+    where
+        F: Fn(&[ScreenEvent]) -> bool,
+    {
+        // Subscribing first so that nothing displayed in between is missed
+        let mut stream = self.event_stream_timeout(timeout).await?;
+        let mut events = self.events(true).await?;
+
+        while !predicate(&events) {
+            match stream.next().await {
+                Some(event) => events.push(event?),
+                None => return Err(SpeculosError::Timeout),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Waits until a text element satisfying `predicate` is displayed, for at most `timeout`,
+    /// returning the element.
+    ///
+    /// The current screen is checked first, then every text element subsequently displayed. The
+    /// returned event carries the coordinates of the text, which can be used for a subsequent
+    /// [`touch`](Self::touch) on touchscreen devices.
+    pub async fn poll_until<F>(
+        &self,
+        predicate: F,
+        timeout: Duration,
+    ) -> Result<ScreenEvent, SpeculosError>
+    where
+        F: Fn(&ScreenEvent) -> bool,
+    {
+        // Subscribing first so that nothing displayed in between is missed
+        let mut stream = self.event_stream_timeout(timeout).await?;
+        if let Some(event) = self.events(true).await?.into_iter().find(&predicate) {
+            return Ok(event);
+        }
+
+        while let Some(event) = stream.next().await {
+            let event = event?;
+            if predicate(&event) {
+                return Ok(event);
+            }
+        }
+
+        Err(SpeculosError::Timeout)
+    }
+
+    /// Runs `during` to completion while asserting that no text element containing `text` is
+    /// displayed, returning the output of `during`.
+    ///
+    /// The current screen is checked before and after `during` runs, and every text element
+    /// displayed in between is checked as it appears. Once a match is found, `during` is dropped
+    /// without being polled further and [`SpeculosError::ForbiddenText`] is returned. Should
+    /// Speculos close the event stream early, only the final screen is checked.
+    pub async fn assert_never_shows<F>(
+        &self,
+        text: &str,
+        during: F,
+    ) -> Result<F::Output, SpeculosError>
+    where
+        F: Future,
+    {
+        // Subscribing first so that nothing displayed in between is missed
+        let mut stream = self.event_stream().await?;
+        self.assert_screen_lacks(text).await?;
+
+        let watch = async {
+            while let Some(event) = stream.next().await {
+                let event = event?;
+                if event.text.contains(text) {
+                    return Err(SpeculosError::ForbiddenText { text: event.text });
+                }
+            }
+
+            Ok(())
+        };
+
+        let output = match select(std::pin::pin!(during), std::pin::pin!(watch)).await {
+            Either::Left((output, _)) => output,
+            Either::Right((Ok(()), during)) => during.await,
+            Either::Right((Err(err), _)) => return Err(err),
+        };
+        self.assert_screen_lacks(text).await?;
+
+        Ok(output)
+    }
+
+    async fn assert_screen_lacks(&self, text: &str) -> Result<(), SpeculosError> {
+        match self
+            .events(true)
+            .await?
+            .into_iter()
+            .find(|event| event.text.contains(text))
+        {
+            Some(event) => Err(SpeculosError::ForbiddenText { text: event.text }),
+            None => Ok(()),
+        }
+    }
+
+    /// Finds the first text element on the current screen containing `text`.
+    ///
+    /// The returned event carries the coordinates of the text, which can be used for a subsequent
+    /// [`touch`](Self::touch) on touchscreen devices.
+    pub async fn find_text(&self, text: &str) -> Result<Option<ScreenEvent>, SpeculosError> {
+        Ok(self
+            .events(true)
+            .await?
+            .into_iter()
+            .find(|event| event.text.contains(text)))
+    }
+
+    /// Gets the text elements on the current screen positioned within `rect`.
+    ///
+    /// An element matches when its coordinates fall within `rect` as defined by [`Rect::contains`].
+    /// Only the coordinates reported by Speculos are considered, not the extent of the text.
+    pub async fn events_in_region(&self, rect: Rect) -> Result<Vec<ScreenEvent>, SpeculosError> {
+        let mut events = self.events(true).await?;
+        events.retain(|event| rect.contains(event.x, event.y));
+
+        Ok(events)
+    }
+
+    /// Gets the text elements on the current screen, excluding those within the status bar.
+    ///
+    /// This keeps text matching stable against the changing clock and battery level. The status
+    /// bar area is given by [`DeviceModel::status_bar_region`]; on models without a status bar
+    /// this is equivalent to [`events(true)`](Self::events).
+    pub async fn events_without_status_bar(&self) -> Result<Vec<ScreenEvent>, SpeculosError> {
+        let mut events = self.events(true).await?;
+        if let Some(status_bar) = self.config.model.status_bar_region() {
+            events.retain(|event| !status_bar.contains(event.x, event.y));
+        }
+
+        Ok(events)
+    }
+
+    /// Checks whether the app home screen is displayed.
+    ///
+    /// The home screen is detected by looking for `home_text` on the current screen. When `None`,
+    /// the default text of the standard home screen is used: `"is ready"` on Nano and Blue
+    /// models, and `"Quit app"` on Stax and Flex.
+    pub async fn is_at_home(&self, home_text: Option<&str>) -> Result<bool, SpeculosError> {
+        let home_text = home_text.unwrap_or(self.config.model.home_text());
+
+        Ok(screen_contains(&self.events(true).await?, home_text))
+    }
+
+    /// Presses and releases a button.
+    pub async fn press_button(&self, button: Button) -> Result<(), SpeculosError> {
+        self.post_action(
+            &format!("button/{}", button.slug()),
+            &PostButtonRequest {
+                action: "press-and-release",
+            },
+        )
+        .await
+    }
+
+    /// Presses and releases both buttons.
+    pub async fn press_both_buttons(&self) -> Result<(), SpeculosError> {
+        self.post_action(
+            "button/both",
+            &PostButtonRequest {
+                action: "press-and-release",
+            },
+        )
+        .await
+    }
+
+    /// Touches and releases the screen at the given coordinates.
+    ///
+    /// Coordinates outside of [`DeviceModel::screen_size`] result in
+    /// [`SpeculosError::TouchOutOfBounds`], as Speculos would silently ignore them.
+    pub async fn touch(&self, x: u32, y: u32) -> Result<(), SpeculosError> {
+        self.check_touch(x, y)?;
+
+        self.post_action(
+            "finger",
+            &PostFingerRequest {
+                action: "press-and-release",
+                x,
+                y,
+            },
+        )
+        .await
+    }
+
+    /// Touches the screen at the given coordinates, holds for `duration`, then releases.
+    pub async fn long_press(
+        &self,
+        x: u32,
+        y: u32,
+        duration: Duration,
+    ) -> Result<(), SpeculosError> {
+        self.finger_sequence(&[
+            FingerEvent {
+                x,
+                y,
+                touched: true,
+                delay: duration,
+            },
+            FingerEvent {
+                x,
+                y,
+                touched: false,
+                delay: Duration::ZERO,
+            },
+        ])
+        .await
+    }
+
+    /// Performs a sequence of touchscreen state changes, waiting for each event's delay in
+    /// between.
+    ///
+    /// All coordinates are checked as with [`touch`](Self::touch) before any event is performed.
+    pub async fn finger_sequence(&self, events: &[FingerEvent]) -> Result<(), SpeculosError> {
+        for event in events {
+            self.check_touch(event.x, event.y)?;
+        }
+
+        for event in events {
+            self.post_action(
+                "finger",
+                &PostFingerRequest {
+                    action: if event.touched { "press" } else { "release" },
+                    x: event.x,
+                    y: event.y,
+                },
+            )
+            .await?;
+
+            if !event.delay.is_zero() {
+                tokio::time::sleep(event.delay).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_touch(&self, x: u32, y: u32) -> Result<(), SpeculosError> {
+        let (width, height) = self.config.model.screen_size();
+        if x >= width || y >= height {
+            return Err(SpeculosError::TouchOutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Performs a single [`Gesture`].
+    pub async fn gesture(&self, gesture: Gesture) -> Result<(), SpeculosError> {
+        match gesture {
+            Gesture::Button(button) => self.press_button(button).await,
+            Gesture::BothButtons => self.press_both_buttons().await,
+            Gesture::Tap { x, y } => self.touch(x, y).await,
+            Gesture::LongPress { x, y, duration } => self.long_press(x, y, duration).await,
+        }
+    }
+
+    /// Sends an APDU command that requires user approval, performing the approval concurrently.
+    ///
+    /// The APDU response is returned once the approval has been performed. The whole process is
+    /// subject to the APDU timeout.
+    pub async fn sign_with_approval(
+        &self,
+        apdu: &[u8],
+        approval: &ApprovalFlow<'_>,
+    ) -> Result<Vec<u8>, SpeculosError> {
+        let (response, _) = try_join(self.apdu(apdu), self.approve(approval)).await?;
+
+        Ok(response)
+    }
+
+    /// Sends an APDU command while performing `navigation` concurrently, returning the response
+    /// data and status word separately.
+    ///
+    /// This covers APDU commands whose response depends on user input, e.g. checking for `9000`
+    /// after approving a signature, or `6985` after rejecting it. `navigation` is usually built
+    /// with [`navigate`](Self::navigate). If the response arrives before `navigation` completes,
+    /// e.g. when the app refuses the request upfront, `navigation` is abandoned. Errors from
+    /// `navigation` abandon the APDU command instead.
+    pub async fn apdu_with_navigation<F>(
+        &self,
+        apdu: &[u8],
+        navigation: F,
+    ) -> Result<(Vec<u8>, u16), SpeculosError>
+    where
+        F: IntoFuture<Output = Result<(), SpeculosError>>,
+    {
+        let response = std::pin::pin!(self.apdu_split(apdu));
+        let navigation = std::pin::pin!(navigation.into_future());
+
+        match select(response, navigation).await {
+            Either::Left((response, _)) => response,
+            Either::Right((Ok(()), response)) => response.await,
+            Either::Right((Err(err), _)) => Err(err),
+        }
+    }
+
+    /// Waits for `text` to be displayed, then performs `gestures` in order.
+    ///
+    /// Waiting first avoids acting on whatever screen was displayed before a pending operation
+    /// got to the review. Between gestures, the screen is waited to change and settle, for at most
+    /// the automation timeout, so that each gesture acts on the screen brought up by the previous
+    /// one. The whole process is subject to the APDU timeout.
+    pub async fn navigate(&self, text: &str, gestures: &[Gesture]) -> Result<(), SpeculosError> {
+        self.navigate_with_interval(text, gestures, self.config.poll_interval)
+            .await
+    }
+
+    /// Like [`navigate`](Self::navigate), but polling every `interval` instead of the client's
+    /// [poll interval](Self::set_poll_interval).
+    pub async fn navigate_with_interval(
+        &self,
+        text: &str,
+        gestures: &[Gesture],
+        interval: Duration,
+    ) -> Result<(), SpeculosError> {
+        let deadline = Instant::now() + self.apdu_timeout;
+        self.wait_for_text(text, deadline, interval).await?;
+
+        for (ind, gesture) in gestures.iter().enumerate() {
+            let texts = self.screen_texts().await?;
+            self.gesture(*gesture).await?;
+
+            if ind + 1 < gestures.len() {
+                self.wait_for_screen_change(&texts, deadline, interval)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Captures the current screen for use in a [`GoldenSequence`], optionally with a screenshot.
+    pub async fn capture_screen(&self, screenshot: bool) -> Result<GoldenScreen, SpeculosError> {
+        let texts = self.screen_texts().await?;
+        let screenshot = match screenshot {
+            true => Some(self.screenshot().await?),
+            false => None,
+        };
+
+        Ok(GoldenScreen { texts, screenshot })
+    }
+
+    /// Records each distinct screen displayed during `duration`, starting with the current one.
+    ///
+    /// This is meant for capturing a baseline while the app is navigated by a human or by a
+    /// script running concurrently. A screen is recorded once its text stops changing between
+    /// two polls, so transient screens may be missed.
+    pub async fn record_screens(
+        &self,
+        name: &str,
+        duration: Duration,
+        screenshot: bool,
+    ) -> Result<GoldenSequence, SpeculosError> {
+        self.record_screens_with_interval(name, duration, screenshot, self.config.poll_interval)
+            .await
+    }
+
+    /// Like [`record_screens`](Self::record_screens), but polling every `interval` instead of the
+    /// client's [poll interval](Self::set_poll_interval).
+    pub async fn record_screens_with_interval(
+        &self,
+        name: &str,
+        duration: Duration,
+        screenshot: bool,
+        interval: Duration,
+    ) -> Result<GoldenSequence, SpeculosError> {
+        let deadline = Instant::now() + duration;
+        let mut sequence = GoldenSequence::new(name);
+        sequence.push(self.capture_screen(screenshot).await?);
+
+        while Instant::now() < deadline {
+            let previous = &sequence.screens[sequence.screens.len() - 1].texts;
+            let texts = self.next_screen_texts(previous, deadline, interval).await?;
+            if texts != *previous {
+                let screenshot = match screenshot {
+                    true => Some(self.screenshot().await?),
+                    false => None,
+                };
+                sequence.push(GoldenScreen { texts, screenshot });
+            }
+        }
+
+        Ok(sequence)
+    }
+
+    /// Records the screen as an animated GIF written to `path`, capturing a screenshot every
+    /// `interval` for `duration`.
+    ///
+    /// Like [`record_screens`](Self::record_screens), this is meant to run concurrently with the
+    /// navigation being recorded. Each frame is shown for the time that actually elapsed until the
+    /// next capture, so playback follows the real pace of the run even when captures fall behind
+    /// `interval`. Consecutive identical frames are merged. GIF frame delays have a 10ms
+    /// resolution.
+    ///
+    /// A zero `interval` results in [`SpeculosError::ZeroInterval`].
+    #[cfg(feature = "gif")]
+    pub async fn record_gif<P: AsRef<Path>>(
+        &self,
+        path: P,
+        interval: Duration,
+        duration: Duration,
+    ) -> Result<(), SpeculosError> {
+        if interval.is_zero() {
+            return Err(SpeculosError::ZeroInterval);
+        }
+
+        let deadline = tokio::time::Instant::now() + duration;
+        let mut ticks = tokio::time::interval(interval);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        let mut frames: Vec<(image::RgbaImage, Instant)> = Vec::new();
+        while ticks.tick().await < deadline {
+            let captured_at = Instant::now();
+            let png = self.screenshot().await?;
+            let frame =
+                image::load_from_memory_with_format(&png, image::ImageFormat::Png)?.into_rgba8();
+
+            if frames.last().is_none_or(|(last, _)| *last != frame) {
+                frames.push((frame, captured_at));
+            }
+        }
+
+        // The last frame is shown until the end of the recording
+        let end = Instant::now();
+        let delays = frames
+            .iter()
+            .skip(1)
+            .map(|(_, captured_at)| *captured_at)
+            .chain([end])
+            .zip(frames.iter().map(|(_, captured_at)| *captured_at))
+            .map(|(next, current)| next - current)
+            .collect::<Vec<_>>();
+
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = image::codecs::gif::GifEncoder::new(file);
+        encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
+        encoder.encode_frames(frames.into_iter().zip(delays).map(|((frame, _), delay)| {
+            image::Frame::from_parts(frame, 0, 0, image::Delay::from_saturating_duration(delay))
+        }))?;
+
+        Ok(())
+    }
+
+    /// Checks that the screens of `golden` are displayed in order, performing `next` to move from
+    /// one screen to the next.
+    ///
+    /// The first screen is expected to be displayed already. After each `next`, the screen is
+    /// compared once it stops changing, or once the automation timeout elapses. Screenshots are
+    /// only compared for screens that have one. The first mismatch results in
+    /// [`SpeculosError::ScreenMismatch`].
+    pub async fn assert_screen_sequence(
+        &self,
+        golden: &GoldenSequence,
+        next: Gesture,
+    ) -> Result<(), SpeculosError> {
+        self.assert_screen_sequence_with_interval(golden, next, self.config.poll_interval)
+            .await
+    }
+
+    /// Like [`assert_screen_sequence`](Self::assert_screen_sequence), but polling every `interval`
+    /// instead of the client's [poll interval](Self::set_poll_interval).
+    pub async fn assert_screen_sequence_with_interval(
+        &self,
+        golden: &GoldenSequence,
+        next: Gesture,
+        interval: Duration,
+    ) -> Result<(), SpeculosError> {
+        let mut texts = self.screen_texts().await?;
+
+        for (index, expected) in golden.screens.iter().enumerate() {
+            if index > 0 {
+                self.gesture(next).await?;
+                let deadline = Instant::now() + self.automation_timeout;
+                texts = self.next_screen_texts(&texts, deadline, interval).await?;
+            }
+
+            let screenshot_matches = match &expected.screenshot {
+                Some(screenshot) => *screenshot == self.screenshot().await?,
+                None => true,
+            };
+            if texts != expected.texts || !screenshot_matches {
+                return Err(SpeculosError::ScreenMismatch {
+                    index,
+                    expected: expected.texts.clone(),
+                    actual: texts,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn url(&self, path: &str) -> String {
+        // IPv6 addresses must be bracketed in URLs
+        if self.config.host.contains(':') {
+            format!("http://[{}]:{}/{}", self.config.host, self.port, path)
+        } else {
+            format!("http://{}:{}/{}", self.config.host, self.port, path)
+        }
+    }
+
+    /// Gets the text of the current screen in reading order, one line per row.
+    ///
+    /// Text elements are sorted top to bottom, then left to right. Like with
+    /// [`text_at_row`](Self::text_at_row), elements within 2 pixels of the topmost element of a row
+    /// belong to that row, and are joined by a single space.
+    pub async fn screen_text(&self) -> Result<String, SpeculosError> {
+        Ok(rows_text(self.events(true).await?))
+    }
+
+    /// Gets the text of the current screen on the row at `y`, left to right.
+    ///
+    /// Text elements within 2 pixels of `y` are included, as baselines of text on the same row can
+    /// vary slightly, and joined by a single space. Rows without text result in an empty string.
+    pub async fn text_at_row(&self, y: u32) -> Result<String, SpeculosError> {
+        let mut events = self
+            .events(true)
+            .await?
+            .into_iter()
+            .filter(|event| event.y.abs_diff(y) <= ROW_TOLERANCE)
+            .collect::<Vec<_>>();
+        events.sort_by_key(|event| event.x);
+
+        Ok(events
+            .iter()
+            .map(|event| event.text.trim())
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+
+    /// Checks that the [`screen_text`](Self::screen_text) of the current screen is `expected`.
+    ///
+    /// Lines are compared with surrounding whitespace trimmed, and blank lines at the start and
+    /// end of `expected` are ignored, which allows indented multi-line string literals. A mismatch
+    /// results in [`SpeculosError::ScreenTextMismatch`], which displays a line diff.
+    pub async fn assert_screen_text(&self, expected: &str) -> Result<(), SpeculosError> {
+        let expected = trim_lines(expected);
+        let actual = self.screen_text().await?;
+
+        if actual != expected {
+            return Err(SpeculosError::ScreenTextMismatch { expected, actual });
+        }
+
+        Ok(())
+    }
+
+    async fn screen_texts(&self) -> Result<Vec<String>, SpeculosError> {
+        Ok(self
+            .events(true)
+            .await?
+            .into_iter()
+            .map(|event| event.text)
+            .collect())
+    }
+
+    /// Waits for a screen other than `previous` to stop changing, returning the last read screen
+    /// once `deadline` passes.
+    async fn next_screen_texts(
+        &self,
+        previous: &[String],
+        deadline: Instant,
+        interval: Duration,
+    ) -> Result<Vec<String>, SpeculosError> {
+        let mut last = None;
+        loop {
+            let texts = self.screen_texts().await?;
+            if texts != previous && last.as_ref() == Some(&texts) {
+                return Ok(texts);
+            }
+            if Instant::now() >= deadline {
+                return Ok(texts);
+            }
+
+            last = Some(texts);
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    async fn wait_for_text(
+        &self,
+        text: &str,
+        deadline: Instant,
+        interval: Duration,
+    ) -> Result<(), SpeculosError> {
+        while !screen_contains(&self.events(true).await?, text) {
+            if Instant::now() >= deadline {
+                return Err(SpeculosError::Timeout);
+            }
+            tokio::time::sleep(interval).await;
+        }
+
+        Ok(())
+    }
+
+    /// Waits for the screen to change from `previous` and settle, for at most the automation
+    /// timeout, failing once `deadline` passes.
+    async fn wait_for_screen_change(
+        &self,
+        previous: &[String],
+        deadline: Instant,
+        interval: Duration,
+    ) -> Result<(), SpeculosError> {
+        let step_deadline = deadline.min(Instant::now() + self.automation_timeout);
+        self.next_screen_texts(previous, step_deadline, interval)
+            .await?;
+
+        if Instant::now() >= deadline {
+            return Err(SpeculosError::Timeout);
+        }
+        Ok(())
+    }
+
+    async fn approve(&self, approval: &ApprovalFlow<'_>) -> Result<(), SpeculosError> {
+        let deadline = Instant::now() + self.apdu_timeout;
+        let interval = self.config.poll_interval;
+
+        // Navigating before the review starts would act on whatever screen was displayed before
+        self.wait_for_text(&approval.review_text, deadline, interval)
+            .await?;
+
+        loop {
+            let events = self.events(true).await?;
+            if screen_contains(&events, &approval.confirm_text) {
+                break;
+            }
+            if Instant::now() >= deadline {
+                return Err(SpeculosError::Timeout);
+            }
+
+            let texts = events
+                .into_iter()
+                .map(|event| event.text)
+                .collect::<Vec<_>>();
+            self.gesture(approval.next).await?;
+            self.wait_for_screen_change(&texts, deadline, interval)
+                .await?;
+        }
+
+        self.gesture(approval.confirm).await
+    }
+
+    async fn event_stream_inner(
+        &self,
+        deadline: Option<tokio::time::Instant>,
+    ) -> Result<EventStream, SpeculosError> {
+        // No request timeout here as it would apply to the whole body, i.e. the stream lifetime
+        let request = self.client.get(self.url("events?stream=true"));
+        let request = self.send(request);
+        let response = match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, request)
+                .await
+                .map_err(|_| SpeculosError::Timeout)??,
+            None => request.await?,
+        };
+        let body = Box::pin(response.error_for_status()?.bytes_stream());
+
+        let events = futures_util::stream::unfold(
+            (body, Vec::new()),
+            move |(mut body, mut buffer)| async move {
+                loop {
+                    // Server-sent events are line-based, with payloads on `data:` lines
+                    if let Some(position) = buffer.iter().position(|byte| *byte == b'\n') {
+                        let line = buffer.drain(..=position).collect::<Vec<_>>();
+                        if let Some(data) = line.strip_prefix(b"data:") {
+                            let event = serde_json::from_slice::<ScreenEvent>(data.trim_ascii())
+                                .map_err(SpeculosError::from);
+                            return Some((event, (body, buffer)));
+                        }
+                        continue;
+                    }
+
+                    let chunk = match deadline {
+                        Some(deadline) => tokio::time::timeout_at(deadline, body.next())
+                            .await
+                            .ok()??,
+                        None => body.next().await?,
+                    };
+                    match chunk {
+                        Ok(chunk) => buffer.extend_from_slice(&chunk),
+                        Err(error) => return Some((Err(error.into()), (body, buffer))),
+                    }
+                }
+            },
+        );
+
+        Ok(EventStream {
+            inner: Box::pin(events),
+        })
+    }
+
+    fn next_apdu_fault(&self) -> Option<ApduFault> {
+        let index = self.apdu_count.fetch_add(1, Ordering::Relaxed) + 1;
+        self.apdu_faults
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&index)
+    }
+
+    async fn post_automation(&self, body: Vec<u8>) -> Result<(), SpeculosError> {
+        let request = self
+            .client
+            .post(self.url("automation"))
+            .timeout(self.automation_timeout)
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT, "application/json")
+            .body(body);
+        let response = self.send(request).await?;
+
+        if response.status() == StatusCode::BAD_REQUEST {
+            let body = response.bytes().await?;
+            let reason = match serde_json::from_slice::<ErrorResponse>(&body) {
+                Ok(body) => body.error,
+                Err(_) => String::from_utf8_lossy(&body).into_owned(),
+            };
+            return Err(SpeculosError::AutomationRejected { reason });
+        }
+
+        response.error_for_status()?;
+        Ok(())
+    }
+
+    async fn post_action<T: Serialize>(&self, path: &str, body: &T) -> Result<(), SpeculosError> {
+        // Nothing to capture in dry-run mode
+        let screenshot_dir = match &self.config.auto_screenshot {
+            Some(dir) if self.config.dry_run.is_none() => {
+                let step = self.screenshot_step.fetch_add(1, Ordering::Relaxed) + 1;
+                self.save_screenshot(dir, &format!("{:04}-before.png", step))
+                    .await?;
+                Some((dir, step))
+            }
+            _ => None,
+        };
+
+        let request = self
+            .client
+            .post(self.url(path))
+            .timeout(self.automation_timeout)
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT, "application/json")
+            .json(body);
+        let response = self.send(request).await?;
+
+        response.error_for_status()?;
+
+        if let Some((dir, step)) = screenshot_dir {
+            self.save_screenshot(dir, &format!("{:04}-after.png", step))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn save_screenshot(&self, dir: &Path, file_name: &str) -> Result<(), SpeculosError> {
+        let png = self.screenshot().await?;
+
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join(file_name), png)?;
+        Ok(())
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response, SpeculosError> {
+        if self.is_unresponsive() {
+            return Err(SpeculosError::Unresponsive);
+        }
+
+        let request = request.build()?;
+
+        if let Some(on_request) = &self.config.on_request {
+            on_request(&RequestInfo {
+                method: request.method().as_str(),
+                path: request.url().path(),
+                body: request.body().and_then(|body| body.as_bytes()),
+            });
+        }
+
+        if let Some(apdu_response) = &self.config.dry_run {
+            return Ok(dry_run_response(&request, apdu_response));
+        }
+
+        if self.metrics.is_none() && self.config.on_response.is_none() {
+            return match self.client.execute(request).await {
+                Ok(response) => Ok(response),
+                Err(error) => Err(self.request_error(error).await),
+            };
+        }
+
+        let method = request.method().clone();
+        let endpoint = request.url().path().to_owned();
+        let start = Instant::now();
+        let response = match self.client.execute(request).await {
+            Ok(response) => response,
+            Err(error) => return Err(self.request_error(error).await),
+        };
+        let elapsed = start.elapsed();
+
+        if let Some(on_response) = &self.config.on_response {
+            on_response(&ResponseInfo {
+                method: method.as_str(),
+                path: &endpoint,
+                status: response.status().as_u16(),
+                elapsed,
+            });
+        }
+
+        if let Some(metrics) = &self.metrics {
+            metrics
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(endpoint)
+                .and_modify(|metrics| metrics.record(elapsed))
+                .or_insert(EndpointMetrics {
+                    count: 1,
+                    min: elapsed,
+                    max: elapsed,
+                    total: elapsed,
+                });
+        }
+
+        Ok(response)
+    }
+
+    /// Attributes a failed request to the Speculos process having exited, if it did.
+    async fn request_error(&self, error: reqwest::Error) -> SpeculosError {
+        if error.is_timeout() {
+            return error.into();
+        }
+
+        // Connections are closed slightly before the exit status becomes available
+        for attempt in 0..2 {
+            let status = self
+                .process
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .as_mut()
+                .map(Child::try_wait);
+            match status {
+                Some(Ok(Some(status))) => return exit_error(status, &self.output),
+                Some(Ok(None)) if attempt == 0 => {
+                    tokio::time::sleep(self.config.poll_interval).await
+                }
+                _ => break,
+            }
+        }
+
+        error.into()
+    }
+
+    /// Blocking counterpart of [`wait_for_api`](Self::wait_for_api), usable without an async
+    /// runtime.
+    fn wait_for_api_blocking(&mut self, timeout: Duration) -> Result<(), SpeculosError> {
+        // Nothing to wait for in dry-run mode
+        let Some(process) = self
+            .process
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+        else {
+            return Ok(());
+        };
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(status) = process.try_wait()? {
+                return Err(exit_error(status, &self.output));
+            }
+
+            // An open port doesn't mean the API is served yet
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if probe_api(
+                &self.config.host,
+                self.port,
+                remaining.max(PROBE_MIN_TIMEOUT),
+            ) {
+                return Ok(());
+            }
+
+            if Instant::now() >= deadline {
+                return Err(SpeculosError::Timeout);
+            }
+            std::thread::sleep(self.config.poll_interval);
+        }
+    }
+
+    async fn wait_for_api(&mut self, timeout: Duration) -> Result<(), SpeculosError> {
+        let url = self.url("events?currentscreenonly=true");
+        // Nothing to wait for in dry-run mode
+        let Some(process) = self
+            .process
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+        else {
+            return Ok(());
+        };
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(status) = process.try_wait()? {
+                return Err(exit_error(status, &self.output));
+            }
+
+            let response = self
+                .client
+                .get(&url)
+                .timeout(deadline.saturating_duration_since(Instant::now()))
+                .send()
+                .await;
+            if response.is_ok_and(|response| response.status().is_success()) {
+                return Ok(());
+            }
+
+            if Instant::now() >= deadline {
+                return Err(SpeculosError::Timeout);
+            }
+            tokio::time::sleep(self.config.poll_interval).await;
+        }
+    }
+}
+
+impl Drop for SpeculosClient {
+    fn drop(&mut self) {
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.abort();
+        }
+
+        if self.kill_on_drop
+            && let Some(process) = self
+                .process
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner)
+        {
+            let _ = self.config.kill_process(process);
+        }
+    }
+}
+
+impl SpeculosPool {
+    /// Launches `size` instances configured by `builder` concurrently, each on its own
+    /// auto-assigned ports.
+    ///
+    /// The port set on `builder` is ignored. An APDU port is assigned only if one is set on
+    /// `builder`. Should any instance fail to launch, the others are terminated and the error is
+    /// returned.
+    pub async fn new(builder: SpeculosClientBuilder, size: usize) -> Result<Self, SpeculosError> {
+        // Holding on to all listeners until done prevents the same port from being handed out twice
+        let mut listeners = Vec::new();
+        let mut free_port = || -> Result<u16, SpeculosError> {
+            let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+            let port = listener.local_addr()?.port();
+            listeners.push(listener);
+            Ok(port)
+        };
+
+        let mut builders = Vec::with_capacity(size);
+        for _ in 0..size {
+            let mut builder = builder.clone();
+            builder.port = free_port()?;
+            if builder.apdu_port != 0 {
+                builder.apdu_port = free_port()?;
+            }
+            builders.push(builder);
+        }
+        drop(listeners);
+
+        let clients =
+            try_join_all(builders.into_iter().map(|builder| builder.launch_async())).await?;
+
+        let available = Arc::new(Semaphore::new(size));
+        if size == 0 {
+            available.close();
+        }
+
+        Ok(Self {
+            inner: Arc::new(PoolInner {
+                idle: Mutex::new(clients.into()),
+                available,
+                live: AtomicUsize::new(size),
+                poll_interval: builder.poll_interval,
+            }),
+        })
+    }
+
+    /// Checks out an instance, waiting for one to be returned if none is available.
+    ///
+    /// Waiting callers are served in the order they called this method. Once no instance is left,
+    /// i.e. the pool was created empty or all instances failed to reset, this and any pending call
+    /// fail with [`SpeculosError::PoolExhausted`] instead of waiting forever.
+    pub async fn get(&self) -> Result<PooledClient, SpeculosError> {
+        let permit = self
+            .inner
+            .available
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| SpeculosError::PoolExhausted)?;
+
+        // A permit is only ever available alongside an idle client
+        let client = self
+            .inner
+            .idle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front()
+            .ok_or(SpeculosError::PoolExhausted)?;
+
+        Ok(PooledClient {
+            client: Some(client),
+            permit: Some(permit),
+            pool: self.inner.clone(),
+        })
+    }
+
+    /// Gets the number of instances available for checkout without waiting.
+    pub fn available(&self) -> usize {
+        self.inner.available.available_permits()
+    }
+}
+
+impl PoolInner {
+    /// Accounts for a terminated instance whose permit was forgotten.
+    fn retire(&self) {
+        if self.live.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.available.close();
+        }
+    }
+}
+
+impl std::ops::Deref for PooledClient {
+    type Target = SpeculosClient;
+
+    fn deref(&self) -> &Self::Target {
+        self.client.as_ref().expect("client present until drop")
+    }
+}
+
+impl std::ops::DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.client.as_mut().expect("client present until drop")
+    }
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        let (Some(mut client), Some(permit)) = (self.client.take(), self.permit.take()) else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            permit.forget();
+            self.pool.retire();
+            return;
+        };
+        let pool = self.pool.clone();
+
+        runtime.spawn(async move {
+            if let Some(watchdog) = client.watchdog.take() {
+                watchdog.abort();
+            }
+            client.config.poll_interval = pool.poll_interval;
+            if let Some(metrics) = &mut client.metrics {
+                metrics
+                    .get_mut()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clear();
+            }
+            *client.screenshot_step.get_mut() = 0;
+            client.automation_persistent = false;
+            *client
+                .last_automation
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner) = None;
+            client
+                .apdu_faults
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+
+            match client.reboot().await {
+                Ok(()) => {
+                    pool.idle
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .push_back(client);
+                    drop(permit);
+                }
+                Err(_) => {
+                    permit.forget();
+                    pool.retire();
+                }
+            }
+        });
+    }
+}
+
+impl SpeculosClientBuilder {
+    /// Creates a [`SpeculosClientBuilder`] from environment variables:
+    ///
+    /// - `SPECULOS_MODEL` (required): device model slug, e.g. `nanosp`.
+    /// - `SPECULOS_APP` (required): path to the app.
+    /// - `SPECULOS_PORT`: port of the HTTP API. Defaults to `5000`.
+    /// - `SPECULOS_SEED`: BIP39 mnemonic used as the device seed.
+    pub fn from_env() -> Result<Self, SpeculosError> {
+        fn var(name: &'static str) -> Result<Option<String>, SpeculosError> {
+            match std::env::var(name) {
+                Ok(value) => Ok(Some(value)),
+                Err(std::env::VarError::NotPresent) => Ok(None),
+                Err(std::env::VarError::NotUnicode(value)) => Err(SpeculosError::InvalidEnvVar {
+                    name,
+                    value: value.to_string_lossy().into_owned(),
+                }),
+            }
+        }
+
+        let model = var("SPECULOS_MODEL")?.ok_or(SpeculosError::MissingEnvVar("SPECULOS_MODEL"))?;
+        let model = model
+            .parse::<DeviceModel>()
+            .map_err(|_| SpeculosError::InvalidEnvVar {
+                name: "SPECULOS_MODEL",
+                value: model,
+            })?;
+        let app = var("SPECULOS_APP")?.ok_or(SpeculosError::MissingEnvVar("SPECULOS_APP"))?;
+        let port = match var("SPECULOS_PORT")? {
+            Some(port) => port
+                .parse::<u16>()
+                .map_err(|_| SpeculosError::InvalidEnvVar {
+                    name: "SPECULOS_PORT",
+                    value: port,
+                })?,
+            None => 5000,
+        };
+
+        let builder = SpeculosClient::builder(model, port, app);
+        Ok(match var("SPECULOS_SEED")? {
+            Some(seed) => builder.seed(seed),
+            None => builder,
+        })
+    }
+
+    /// Sets the port of the Speculos APDU TCP server. Defaults to `0`, which disables it.
+    ///
+    /// The TCP server runs alongside the HTTP API, allowing APDUs to be sent with
+    /// [`SpeculosClient::apdu_tcp`] while driving the UI via the API.
+    pub fn apdu_port(mut self, port: u16) -> Self {
+        self.apdu_port = port;
+        self
+    }
+
+    /// Sets the host to connect to Speculos on. Defaults to `127.0.0.1`.
+    ///
+    /// The IPv4 loopback address is used rather than `localhost`, which may resolve to `::1` on
+    /// dual-stack machines while Speculos only listens on IPv4. IPv6 addresses are given without
+    /// brackets, e.g. `::1`. This only changes where the client connects to, not where Speculos
+    /// listens.
+    pub fn host<S: Into<String>>(mut self, host: S) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Sets the maximum time to wait for Speculos to become ready. Defaults to 10 seconds.
+    pub fn launch_timeout(mut self, timeout: Duration) -> Self {
+        self.launch_timeout = timeout;
+        self
+    }
+
+    /// Sets the timeout for APDU requests. Defaults to 10 seconds.
+    ///
+    /// APDUs requiring user interaction only return after the interaction completes, so this
+    /// should account for any navigation performed concurrently.
+    pub fn apdu_timeout(mut self, timeout: Duration) -> Self {
+        self.apdu_timeout = timeout;
+        self
+    }
+
+    /// Sets the timeout for automation requests and other UI-related requests, such as taking
+    /// screenshots. Defaults to 10 seconds.
+    pub fn automation_timeout(mut self, timeout: Duration) -> Self {
+        self.automation_timeout = timeout;
+        self
+    }
+
+    /// Sets an extra delay after Speculos is detected as ready, including after a
+    /// [`SpeculosClient::reboot`]. Defaults to zero.
+    ///
+    /// Some apps need a moment after startup before handling APDUs reliably. Prefer
+    /// [`launch_async`](Self::launch_async), whose API-based readiness detection usually makes
+    /// this unnecessary.
+    pub fn post_ready_delay(mut self, delay: Duration) -> Self {
+        self.post_ready_delay = delay;
+        self
+    }
+
+    /// Sets the interval between polls of helpers waiting on Speculos, such as readiness detection,
+    /// [`SpeculosClient::wait_for_exit`] and screen comparisons. Defaults to 100 milliseconds.
+    ///
+    /// Shorter intervals reduce latency at the cost of more API requests. Helpers based on the
+    /// event stream, such as [`SpeculosClient::watch_for`], don't poll and are unaffected. The
+    /// interval can be changed later with [`SpeculosClient::set_poll_interval`], or overridden
+    /// per call with the `*_with_interval` variants of the helpers.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Sets the schema version sent with automation rules. Defaults to `1`.
+    ///
+    /// This is only needed for Speculos versions that interpret rules under a different schema.
+    pub fn automation_version(mut self, version: u32) -> Self {
+        self.automation_version = version;
+        self
+    }
+
+    /// Sets the JSON field holding the hex-encoded APDU in `POST /apdu` requests. Defaults to
+    /// `"data"`.
+    ///
+    /// This is only needed for Speculos forks expecting another field, e.g. `"apdu"`. Responses
+    /// are always read from `"data"`.
+    pub fn apdu_field<S: Into<String>>(mut self, field: S) -> Self {
+        self.apdu_field = field.into();
+        self
+    }
+
+    /// Sets whether to negotiate gzip/deflate compression for responses. Defaults to `false`.
+    ///
+    /// Response bodies are transparently decompressed, so the returned data is identical either
+    /// way. Compression only helps when talking to Speculos over a slow link through a proxy that
+    /// compresses responses, as Speculos itself does not. Over a local connection it only adds CPU
+    /// overhead.
+    #[cfg(feature = "compression")]
+    pub fn compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets how long idle API connections are kept open for reuse. Defaults to the `reqwest`
+    /// default of 90 seconds.
+    ///
+    /// Connections are reused across requests, so this rarely needs changing unless requests are
+    /// spaced further apart than the timeout.
+    pub fn pool_idle_timeout(mut self, pool_idle_timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(pool_idle_timeout);
+        self
+    }
+
+    /// Sets the maximum number of idle API connections kept open for reuse. Defaults to the
+    /// `reqwest` default of no limit.
+    ///
+    /// As all requests go to the same host, this caps how many connections survive a burst of
+    /// concurrent requests.
+    pub fn pool_max_idle_per_host(mut self, pool_max_idle_per_host: usize) -> Self {
+        self.pool_max_idle_per_host = Some(pool_max_idle_per_host);
+        self
+    }
+
+    /// Sets the TCP keepalive interval for API connections. Defaults to the `reqwest` default.
+    pub fn tcp_keepalive(mut self, tcp_keepalive: Duration) -> Self {
+        self.tcp_keepalive = Some(tcp_keepalive);
+        self
+    }
+
+    /// Adds a header sent with every API request, e.g. `Authorization` for a Speculos instance
+    /// behind an authenticating proxy. Setting the same header again replaces its value.
+    ///
+    /// Header values are redacted from the [`Debug`](std::fmt::Debug) output of the builder.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Sets the BIP39 mnemonic used as the device seed. Defaults to the Speculos built-in seed.
+    ///
+    /// The seed is redacted from the [`Debug`](std::fmt::Debug) output of the builder.
+    pub fn seed<S: Into<String>>(mut self, seed: S) -> Self {
+        self.seed = Some(seed.into());
+        self
+    }
+
+    /// Sets the SDK version of the app, passed to Speculos as `--sdk`.
+    ///
+    /// This is only supported by Nano and Blue models. Stax and Flex apps are identified by API
+    /// level instead, see [`api_level`](Self::api_level). Versions not listed in
+    /// [`DeviceModel::supported_sdks`] are rejected on launch.
+    pub fn sdk<S: Into<String>>(mut self, sdk: S) -> Self {
+        self.sdk = Some(sdk.into());
+        self
+    }
+
+    /// Sets the API level of the app, passed to Speculos as `--apiLevel`.
+    ///
+    /// This is only supported by Nano X, Nano S Plus, Stax and Flex models. Levels outside of
+    /// [`DeviceModel::supported_api_levels`] are rejected on launch.
+    pub fn api_level(mut self, api_level: u32) -> Self {
+        self.api_level = Some(api_level);
+        self
+    }
+
+    /// Sets whether to read the API level from the app ELF when neither [`sdk`](Self::sdk) nor
+    /// [`api_level`](Self::api_level) is set. Defaults to `false`.
+    ///
+    /// The API level is read from the `ledger.api_level` section embedded by recent Ledger SDKs,
+    /// and passed as `--apiLevel` on models supporting it. Apps without the section, such as those
+    /// built with older SDKs, launch without `--apiLevel` as usual.
+    pub fn detect_api_level(mut self, detect_api_level: bool) -> Self {
+        self.detect_api_level = detect_api_level;
+        self
+    }
+
+    /// Sets the stderr line content signaling that Speculos is ready. Defaults to
+    /// `"launcher: using default app name & version"`.
+    ///
+    /// This only affects [`launch`](Self::launch). An empty marker disables stderr-based
+    /// detection in favor of polling the API until it serves the current screen, as does keeping
+    /// the default marker along with an [app name](Self::app_name).
+    pub fn ready_marker<S: Into<String>>(mut self, ready_marker: S) -> Self {
+        self.ready_marker = ready_marker.into();
+        self
+    }
+
+    /// Appends a stderr line content signaling that Speculos failed to start.
+    ///
+    /// The default patterns are `"Traceback"`, `"error:"` and `"Cannot load app"`. Like the
+    /// [ready marker](Self::ready_marker), patterns only affect [`launch`](Self::launch), which
+    /// fails with [`SpeculosError::StartupFailed`] as soon as one shows up before the ready
+    /// marker, instead of waiting for the launch timeout.
+    pub fn failure_pattern<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.failure_patterns.push(pattern.into());
+        self
+    }
+
+    /// Replaces all stderr line contents signaling that Speculos failed to start, including the
+    /// default ones.
+    ///
+    /// See [`failure_pattern`](Self::failure_pattern) for how patterns are used. No patterns at
+    /// all disables failure detection.
+    pub fn failure_patterns<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.failure_patterns = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets whether to collect latency statistics of API requests. Defaults to `false`.
+    ///
+    /// Collected statistics are available from [`SpeculosClient::metrics`]. Requests are covered
+    /// the same way as by [`on_response`](Self::on_response).
+    pub fn metrics(mut self, metrics: bool) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Sets a hook invoked before each API request is sent.
+    ///
+    /// This covers every HTTP API request made by [`SpeculosClient`] methods, including in dry-run
+    /// mode. It doesn't cover:
+    ///
+    /// - readiness polling while launching, before the client is returned;
+    /// - pings sent by [`SpeculosClient::start_watchdog`];
+    /// - APDUs answered by [`SpeculosClient::inject_apdu_fault`] without reaching Speculos;
+    /// - APDUs sent via [`SpeculosClient::apdu_tcp`].
+    ///
+    /// The hook runs on the calling task, so it should return quickly. Panicking in the hook, e.g.
+    /// on an assertion, propagates to the caller.
+    pub fn on_request<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&RequestInfo<'_>) + Send + Sync + 'static,
+    {
+        self.on_request = Some(Arc::new(hook));
+        self
+    }
+
+    /// Sets a hook invoked after each API response is received.
+    ///
+    /// This covers the same requests as [`on_request`](Self::on_request), except that it's not
+    /// invoked in dry-run mode or when no response is received.
+    pub fn on_response<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ResponseInfo<'_>) + Send + Sync + 'static,
+    {
+        self.on_response = Some(Arc::new(hook));
+        self
+    }
+
+    /// Captures screenshots into `dir` before and after each button press and touchscreen event.
+    ///
+    /// Files are named by step index, e.g. `0001-before.png` and `0001-after.png`, giving a
+    /// frame-by-frame trace of the navigation performed. Each step costs two extra API requests,
+    /// so this is best enabled only when debugging. The directory is created if missing.
+    pub fn auto_screenshot<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.auto_screenshot = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets whether Speculos traces the syscalls made by the app, passed as `--trace`. Defaults to
+    /// `false`.
+    ///
+    /// Traces are printed to the process output, which is available from
+    /// [`SpeculosClient::output`].
+    pub fn trace(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// Sets whether Speculos renders graphical elements step by step, passed as `--progressive`.
+    /// Defaults to `false`.
+    pub fn progressive(mut self, progressive: bool) -> Self {
+        self.progressive = progressive;
+        self
+    }
+
+    /// Sets the app name and version reported by Speculos, in `name:version` form, passed as the
+    /// `SPECULOS_APPNAME` environment variable.
+    ///
+    /// Speculos uses it for apps that don't embed their name and version. It then shows in the
+    /// response to the app name and version APDU, and on the home screen of apps displaying them.
+    /// Setting a known name keeps such home screens predictable, e.g. for
+    /// [`SpeculosClient::is_at_home`].
+    ///
+    /// The default [ready marker](Self::ready_marker) is only printed when no app name is set, so
+    /// [`launch`](Self::launch) then polls the API instead, unless a custom marker is set.
+    pub fn app_name<S: Into<String>>(mut self, app_name: S) -> Self {
+        self.app_name = Some(app_name.into());
+        self
+    }
+
+    /// Appends a raw argument to the `speculos` command.
+    ///
+    /// Raw arguments are placed after all typed options and before the app path. As Speculos
+    /// lets the last occurrence of an option win, raw arguments take precedence over typed
+    /// options they duplicate.
+    pub fn arg<S: Into<String>>(mut self, arg: S) -> Self {
+        self.extra_args.push(arg.into());
+        self
+    }
+
+    /// Appends raw arguments to the `speculos` command.
+    ///
+    /// See [`arg`](Self::arg) for how raw arguments are placed.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extra_args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the Python interpreter used to run Speculos as a module, e.g. one from a virtualenv.
+    ///
+    /// This implies [`invoke_as_module`](Self::invoke_as_module).
+    pub fn python<P: AsRef<Path>>(mut self, python: P) -> Self {
+        self.python = Some(python.as_ref().to_path_buf());
+        self.invoke_as_module = true;
+        self
+    }
+
+    /// Sets whether to launch Speculos as `python3 -m speculos` instead of the `speculos` command.
+    /// Defaults to `false`.
+    ///
+    /// This is needed when Speculos is installed as a module without its console script. The
+    /// interpreter can be changed with [`python`](Self::python).
+    pub fn invoke_as_module(mut self, invoke_as_module: bool) -> Self {
+        self.invoke_as_module = invoke_as_module;
+        self
+    }
+
+    /// Sets the path to the `speculos` command.
+    ///
+    /// By default, `speculos` is looked up from `PATH`, falling back to these common install
+    /// locations in order:
+    ///
+    /// - `~/.local/bin/speculos`
+    /// - `.venv/bin/speculos`, relative to the current directory
+    /// - `/opt/speculos/bin/speculos`
+    ///
+    /// Setting an executable disables this lookup.
+    pub fn executable<P: AsRef<Path>>(mut self, executable: P) -> Self {
+        self.executable = Some(executable.as_ref().to_path_buf());
+        self
+    }
+
+    /// Launches Speculos in a Docker container from `image` instead of running it natively, e.g.
+    /// with `ghcr.io/ledgerhq/speculos`.
+    ///
+    /// The image entrypoint must be Speculos itself, as with the official image. The app directory
+    /// is mounted read-only into the container, and the API and APDU ports are published on
+    /// `127.0.0.1`. Other paths passed as raw [`args`](Self::args) are not mounted.
+    ///
+    /// The container is removed with `docker rm -f` whenever the process would be terminated.
+    #[cfg(feature = "docker")]
+    pub fn docker<S: Into<String>>(mut self, image: S) -> Self {
+        self.docker_image = Some(image.into());
+        self
+    }
+
+    /// Sets whether the launched Speculos process keeps running after the client is dropped.
+    /// Defaults to `false`.
+    ///
+    /// This is useful for sharing a single emulator instance across tests or test runs, where
+    /// later clients attach to it with [`connect`](Self::connect). Unlike
+    /// [`SpeculosClient::into_detached`], the process output is discarded rather than captured, so
+    /// the process doesn't depend on the current process to keep running. Readiness is thus
+    /// detected by polling the API. The process is still terminated if it fails to become ready.
+    pub fn detached(mut self, detached: bool) -> Self {
+        self.detached = detached;
+        self
+    }
+
+    /// Enables dry-run mode, where no Speculos process is launched and requests are answered
+    /// locally instead of being sent.
+    ///
+    /// APDUs are answered with `apdu_response`, and other requests succeed with empty responses.
+    /// This is useful for iterating on test logic without a running emulator. Every request still
+    /// passes through the [`on_request`](Self::on_request) hook, which can be used to log or
+    /// assert on them.
+    pub fn dry_run<R: Into<Vec<u8>>>(mut self, apdu_response: R) -> Self {
+        self.dry_run = Some(apdu_response.into());
+        self
+    }
+
+    /// Gets the command line launching Speculos with the current options, quoted for POSIX shells.
+    ///
+    /// This allows running Speculos manually to investigate launch failures. Environment
+    /// variables set for Speculos are prepended, and the seed is redacted. When neither an
+    /// [`executable`](Self::executable) nor another launch method is set, the program is shown as
+    /// `speculos`, even if a fallback location ends up being used.
+    pub fn launch_command_string(&self) -> Result<String, SpeculosError> {
+        let command = self.launch_command(None)?;
+
+        let mut redact = false;
+        let env = command.get_envs().filter_map(|(key, value)| {
+            let value = value?;
+            Some(shell_quote(&format!(
+                "{}={}",
+                key.to_string_lossy(),
+                value.to_string_lossy()
+            )))
+        });
+        let words = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|word| {
+                let word = match redact {
+                    true => "<redacted>".into(),
+                    false => word.to_string_lossy(),
+                };
+                redact = word == "--seed";
+                shell_quote(&word)
+            });
+        let words = env.chain(words).collect::<Vec<_>>();
+
+        Ok(words.join(" "))
+    }
+
+    /// Launches Speculos, blocking the current thread until it's ready.
+    ///
+    /// Readiness is detected by monitoring the process's stderr output for the
+    /// [ready marker](Self::ready_marker). Lines matching a
+    /// [failure pattern](Self::failure_pattern) before that result in
+    /// [`SpeculosError::StartupFailed`].
+    pub fn launch(self) -> Result<SpeculosClient, SpeculosError> {
+        // The default marker reads `using default app name & version`, which Speculos only prints
+        // without an app name
+        let no_marker = self.ready_marker.is_empty()
+            || (self.app_name.is_some() && self.ready_marker == DEFAULT_READY_MARKER);
+        if no_marker || self.detached {
+            let mut client = self.spawn(Stdio::null)?;
+            client.wait_for_api_blocking(self.launch_timeout)?;
+            std::thread::sleep(self.post_ready_delay);
+            client.kill_on_drop = !self.detached;
+            return Ok(client);
+        }
+
+        let mut client = self.spawn(Stdio::piped)?;
+        let Some(process) = client
+            .process
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+        else {
+            // Nothing to wait for in dry-run mode
+            return Ok(client);
+        };
+
+        // Wait for process to be ready by monitoring stderr. The reader thread keeps draining
+        // stderr afterwards so that the process never blocks on a full pipe. `None` is sent upon
+        // startup failure.
+        let (ready_tx, ready_rx) = mpsc::channel();
+        if let Some(stderr) = process.stderr.take() {
+            let ready_marker = self.ready_marker.clone();
+            let failure_patterns = self.failure_patterns.clone();
+            let output = client.output.clone();
+            std::thread::spawn(move || {
+                let mut ready_tx = Some(ready_tx);
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    let ready_line = line.contains(&ready_marker).then(|| line.clone());
+                    let failed = failure_patterns
+                        .iter()
+                        .any(|pattern| line.contains(pattern.as_str()));
+
+                    // Failure lines must be captured before reporting them
+                    {
+                        let mut output = output.lock().unwrap_or_else(PoisonError::into_inner);
+                        if output.len() == OUTPUT_CAPACITY {
+                            output.pop_front();
+                        }
+                        output.push_back(line);
+                    }
+
+                    if (ready_line.is_some() || failed)
+                        && let Some(ready_tx) = ready_tx.take()
+                    {
+                        let _ = ready_tx.send(ready_line);
+                    }
+                }
+            });
+        }
+
+        match ready_rx.recv_timeout(self.launch_timeout) {
+            Ok(Some(ready_line)) => {
+                client.launcher_info = Some(self.launcher_info(Some(ready_line)));
+                std::thread::sleep(self.post_ready_delay);
+                Ok(client)
+            }
+            Ok(None) => {
+                // Give Speculos a moment to print the rest, e.g. a whole Python traceback
+                let deadline = Instant::now() + STARTUP_FAILURE_GRACE;
+                while Instant::now() < deadline && matches!(process.try_wait(), Ok(None)) {
+                    std::thread::sleep(self.poll_interval);
+                }
+
+                let output = client.output.lock().unwrap_or_else(PoisonError::into_inner);
+                let start = output
+                    .iter()
+                    .position(|line| {
+                        self.failure_patterns
+                            .iter()
+                            .any(|pattern| line.contains(pattern.as_str()))
+                    })
+                    .unwrap_or_default();
+                Err(SpeculosError::StartupFailed {
+                    output: output
+                        .iter()
+                        .skip(start)
+                        .take(CRASH_OUTPUT_LINES)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                })
+            }
+            Err(RecvTimeoutError::Timeout) => Err(SpeculosError::Timeout),
+            // The reader thread only stops once stderr is closed, i.e. the process exited
+            Err(RecvTimeoutError::Disconnected) => Err(exit_error(process.wait()?, &client.output)),
+        }
+    }
+
+    /// Launches Speculos without blocking the current thread.
+    ///
+    /// Readiness is detected by polling the API, which makes it safe to launch multiple instances
+    /// concurrently from async code.
+    pub async fn launch_async(self) -> Result<SpeculosClient, SpeculosError> {
+        let mut client = self.spawn(Stdio::null)?;
+
+        // The process is killed on drop should readiness polling fail
+        client.wait_for_api(self.launch_timeout).await?;
+        tokio::time::sleep(self.post_ready_delay).await;
+        client.kill_on_drop = !self.detached;
+
+        Ok(client)
+    }
+
+    /// Attaches to a Speculos instance already serving the API on the configured host and port,
+    /// e.g. one launched [detached](Self::detached), instead of launching one.
+    ///
+    /// Options only affecting the launch are ignored. The client doesn't own the process, which
+    /// is thus never terminated by the client, and process-related methods such as
+    /// [`SpeculosClient::reboot`] and [`SpeculosClient::pause`] behave as in dry-run mode. Fails
+    /// unless the API answers right away.
+    pub async fn connect(self) -> Result<SpeculosClient, SpeculosError> {
+        let mut client = self.build_client(None)?;
+        client.kill_on_drop = false;
+        client.events(true).await?;
+
+        Ok(client)
+    }
+
+    fn spawn(&self, stderr: fn() -> Stdio) -> Result<SpeculosClient, SpeculosError> {
+        let process = match self.dry_run {
+            Some(_) => None,
+            None => {
+                // Speculos fails deep into its startup otherwise
+                if self.app.to_string_lossy().trim().is_empty() {
+                    return Err(SpeculosError::EmptyAppPath);
+                }
+                if !self.app.exists() {
+                    return Err(SpeculosError::AppNotFound(self.app.clone()));
+                }
+                if !self.app.is_file() {
+                    return Err(SpeculosError::AppNotAFile(self.app.clone()));
+                }
+                self.validate()?;
+                self.check_ports()?;
+
+                Some(self.spawn_process(stderr)?)
+            }
+        };
+
+        let mut client = self.build_client(process)?;
+        if self.dry_run.is_none() {
+            client.launcher_info = Some(self.launcher_info(None));
+        }
+
+        Ok(client)
+    }
+
+    fn launcher_info(&self, line: Option<String>) -> LauncherInfo {
+        let identity = line
+            .as_deref()
+            .and_then(line_identity)
+            .or_else(|| self.app_name.as_deref().map(split_identity))
+            .or_else(|| app_identity(&self.app));
+        let (app_name, app_version) = match identity {
+            Some((name, version)) => (Some(name), version),
+            None => (None, None),
+        };
+
+        LauncherInfo {
+            line,
+            app_name,
+            app_version,
+        }
+    }
+
+    fn build_client(&self, process: Option<Child>) -> Result<SpeculosClient, SpeculosError> {
+        let client = ClientBuilder::new();
+        #[cfg(feature = "compression")]
+        let client = client.gzip(self.compression).deflate(self.compression);
+        let mut client = client.default_headers(self.headers.clone());
+        if let Some(tcp_keepalive) = self.tcp_keepalive {
+            client = client.tcp_keepalive(tcp_keepalive);
+        }
+        if let Some(pool_idle_timeout) = self.pool_idle_timeout {
+            client = client.pool_idle_timeout(pool_idle_timeout);
+        }
+        if let Some(pool_max_idle_per_host) = self.pool_max_idle_per_host {
+            client = client.pool_max_idle_per_host(pool_max_idle_per_host);
+        }
+        let client = client.build()?;
+
+        Ok(SpeculosClient {
+            process: Mutex::new(process),
+            kill_on_drop: true,
+            port: self.port,
+            apdu_port: self.apdu_port,
+            client,
+            apdu_timeout: self.apdu_timeout,
+            automation_timeout: self.automation_timeout,
+            launcher_info: None,
+            metrics: self.metrics.then(Default::default),
+            unresponsive: Default::default(),
+            watchdog: None,
+            automation_persistent: false,
+            last_automation: Default::default(),
+            apdu_count: AtomicU64::new(0),
+            apdu_faults: Default::default(),
+            output: Default::default(),
+            apdu_connection: Default::default(),
+            screenshot_step: AtomicU64::new(0),
+            config: self.clone(),
+        })
+    }
+
+    fn spawn_process(&self, stderr: fn() -> Stdio) -> Result<Child, SpeculosError> {
+        #[cfg(feature = "docker")]
+        let docker = self.docker_image.is_some();
+        #[cfg(not(feature = "docker"))]
+        let docker = false;
+
+        if docker || self.invoke_as_module || self.executable.is_some() {
+            return Ok(self.launch_command(None)?.stderr(stderr()).spawn()?);
+        }
+
+        let mut tried = Vec::new();
+        for candidate in std::iter::once(PathBuf::from("speculos")).chain(probe_paths()) {
+            match self
+                .launch_command(Some(&candidate))?
+                .stderr(stderr())
+                .spawn()
+            {
+                Ok(process) => return Ok(process),
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => tried.push(candidate),
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        Err(SpeculosError::SpeculosNotFound { tried })
+    }
+
+    /// Builds the command launching Speculos, using `speculos` or `candidate` as the program unless
+    /// configured otherwise.
+    fn launch_command(&self, candidate: Option<&Path>) -> Result<Command, SpeculosError> {
+        #[cfg(feature = "docker")]
+        if let Some(image) = &self.docker_image {
+            return self.docker_command(image);
+        }
+
+        let mut command = if self.invoke_as_module {
+            let mut command = Command::new(self.python.as_deref().unwrap_or(Path::new("python3")));
+            command.args(["-m", "speculos"]);
+            command
+        } else {
+            Command::new(
+                self.executable
+                    .as_deref()
+                    .or(candidate)
+                    .unwrap_or(Path::new("speculos")),
+            )
+        };
+        command.args(self.command_args());
+        if let Some(app_name) = &self.app_name {
+            command.env(APP_NAME_ENV, app_name);
+        }
+
+        Ok(command)
+    }
+
+    #[cfg(feature = "docker")]
+    fn docker_command(&self, image: &str) -> Result<Command, SpeculosError> {
+        // Docker only accepts absolute paths for bind mounts
+        let app = std::fs::canonicalize(&self.app)?;
+        let (Some(app_dir), Some(app_file)) = (app.parent(), app.file_name()) else {
+            return Err(SpeculosError::AppNotFound(self.app.clone()));
+        };
+
+        // The app path always comes last, and is replaced by its path inside the container
+        let mut args = self.command_args();
+        args.pop();
+        args.push(format!("/speculos/apps/{}", app_file.to_string_lossy()));
+
+        let mut command = Command::new("docker");
+        command
+            .args(["run", "--rm", "--name", &self.container_name()])
+            .arg("-p")
+            .arg(format!("127.0.0.1:{0}:{0}", self.port));
+        if self.apdu_port != 0 {
+            command
+                .arg("-p")
+                .arg(format!("127.0.0.1:{0}:{0}", self.apdu_port));
+        }
+        if let Some(app_name) = &self.app_name {
+            command.arg("-e").arg(format!("{APP_NAME_ENV}={app_name}"));
+        }
+        command
+            .arg("-v")
+            .arg(format!("{}:/speculos/apps:ro", app_dir.display()))
+            .arg(image)
+            .args(args);
+
+        Ok(command)
+    }
+
+    #[cfg(feature = "docker")]
+    fn container_name(&self) -> String {
+        format!("speculos-client-{}-{}", std::process::id(), self.port)
+    }
+
+    fn kill_process(&self, process: &mut Child) -> Result<(), std::io::Error> {
+        process.kill()?;
+
+        // Killing the `docker` client leaves the container running
+        #[cfg(feature = "docker")]
+        if self.docker_image.is_some() {
+            Command::new("docker")
+                .args(["rm", "-f", &self.container_name()])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()?;
+        }
+
+        Ok(())
+    }
+
+    fn check_ports(&self) -> Result<(), SpeculosError> {
+        // Speculos failing to bind is otherwise only noticed once readiness detection times out
+        for port in [self.port, self.apdu_port] {
+            if port != 0 && std::net::TcpListener::bind(("0.0.0.0", port)).is_err() {
+                return Err(SpeculosError::PortInUse(port));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), SpeculosError> {
+        if self.sdk.is_some() && self.api_level.is_some() {
+            return Err(SpeculosError::InvalidLaunchOption(
+                "`sdk` and `api_level` cannot be both set".to_owned(),
+            ));
+        }
+        if self.sdk.is_some() && !self.model.supports_sdk() {
+            return Err(SpeculosError::InvalidLaunchOption(format!(
+                "`sdk` is not supported on {}, use `api_level` instead",
+                self.model.slug()
+            )));
+        }
+        if let Some(sdk) = &self.sdk
+            && !self.model.supported_sdks().contains(&sdk.as_str())
+        {
+            return Err(SpeculosError::InvalidLaunchOption(format!(
+                "unsupported `sdk` {} on {}, expected one of: {}",
+                sdk,
+                self.model.slug(),
+                self.model.supported_sdks().join(", ")
+            )));
+        }
+        if self.api_level.is_some() && !self.model.supports_api_level() {
+            return Err(SpeculosError::InvalidLaunchOption(format!(
+                "`api_level` is not supported on {}, use `sdk` instead",
+                self.model.slug()
+            )));
+        }
+        if let Some(api_level) = self.api_level
+            && let Some(levels) = self.model.supported_api_levels()
+            && !levels.contains(&api_level)
+        {
+            return Err(SpeculosError::InvalidLaunchOption(format!(
+                "unsupported `api_level` {} on {}, expected {} to {}",
+                api_level,
+                self.model.slug(),
+                levels.start(),
+                levels.end()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn command_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "--api-port".to_owned(),
+            self.port.to_string(),
+            "--apdu-port".to_owned(),
+            self.apdu_port.to_string(),
+            "-m".to_owned(),
+            self.model.slug().to_owned(),
+            "--display".to_owned(),
+            "headless".to_owned(),
+        ];
+        if let Some(seed) = &self.seed {
+            args.extend(["--seed".to_owned(), seed.to_owned()]);
+        }
+        if let Some(sdk) = &self.sdk {
+            args.extend(["--sdk".to_owned(), sdk.to_owned()]);
+        }
+        let detected_api_level =
+            if self.detect_api_level && self.sdk.is_none() && self.model.supports_api_level() {
+                app_api_level(&self.app)
+            } else {
+                None
+            };
+        if let Some(api_level) = self.api_level.or(detected_api_level) {
+            args.extend(["--apiLevel".to_owned(), api_level.to_string()]);
+        }
+        if self.trace {
+            args.push("--trace".to_owned());
+        }
+        if self.progressive {
+            args.push("--progressive".to_owned());
+        }
+
+        args.extend(self.extra_args.iter().cloned());
+
+        // The app path must always come last
+        args.push(self.app.display().to_string());
+
+        args
+    }
+}
+
+impl std::fmt::Debug for SpeculosClientBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("SpeculosClientBuilder");
+        debug
+            .field("model", &self.model)
+            .field("port", &self.port)
+            .field("apdu_port", &self.apdu_port)
+            .field("app", &self.app)
+            .field("host", &self.host)
+            .field("launch_timeout", &self.launch_timeout)
+            .field("apdu_timeout", &self.apdu_timeout)
+            .field("automation_timeout", &self.automation_timeout)
+            .field("automation_version", &self.automation_version)
+            .field("apdu_field", &self.apdu_field)
+            .field("post_ready_delay", &self.post_ready_delay)
+            .field("poll_interval", &self.poll_interval);
+        #[cfg(feature = "compression")]
+        debug.field("compression", &self.compression);
+        debug
+            .field("pool_idle_timeout", &self.pool_idle_timeout)
+            .field("pool_max_idle_per_host", &self.pool_max_idle_per_host)
+            .field("tcp_keepalive", &self.tcp_keepalive)
+            .field(
+                "headers",
+                &self
+                    .headers
+                    .keys()
+                    .map(|name| (name.as_str(), "<redacted>"))
+                    .collect::<Vec<_>>(),
+            )
+            .field("seed", &self.seed.as_ref().map(|_| "<redacted>"))
+            .field("sdk", &self.sdk)
+            .field("api_level", &self.api_level)
+            .field("detect_api_level", &self.detect_api_level)
+            .field("ready_marker", &self.ready_marker)
+            .field("failure_patterns", &self.failure_patterns)
+            .field("metrics", &self.metrics)
+            .field("on_request", &self.on_request.as_ref().map(|_| "<hook>"))
+            .field("on_response", &self.on_response.as_ref().map(|_| "<hook>"))
+            .field("auto_screenshot", &self.auto_screenshot)
+            .field("dry_run", &self.dry_run)
+            .field("detached", &self.detached)
+            .field("trace", &self.trace)
+            .field("progressive", &self.progressive)
+            .field("app_name", &self.app_name)
+            .field("extra_args", &self.extra_args)
+            .field("python", &self.python)
+            .field("invoke_as_module", &self.invoke_as_module)
+            .field("executable", &self.executable);
+        #[cfg(feature = "docker")]
+        debug.field("docker_image", &self.docker_image);
+        debug.finish()
+    }
+}
+
+impl DeviceModel {
+    /// Gets the model slug to be used on Speculos.
+    pub const fn slug(&self) -> &'static str {
+        match self {
+            Self::Nanos => "nanos",
+            Self::Nanox => "nanox",
+            Self::Nanosp => "nanosp",
+            Self::Blue => "blue",
+            Self::Stax => "stax",
+            Self::Flex => "flex",
+        }
+    }
+
+    /// Gets the screen resolution of the model in pixels, as `(width, height)`.
+    ///
+    /// | Model       | Resolution |
+    /// |-------------|------------|
+    /// | Nano S      | 128x32     |
+    /// | Nano X      | 128x64     |
+    /// | Nano S Plus | 128x64     |
+    /// | Blue        | 320x480    |
+    /// | Stax        | 400x672    |
+    /// | Flex        | 480x600    |
+    pub const fn screen_size(&self) -> (u32, u32) {
+        match self {
+            Self::Nanos => (128, 32),
+            Self::Nanox | Self::Nanosp => (128, 64),
+            Self::Blue => (320, 480),
+            Self::Stax => (400, 672),
+            Self::Flex => (480, 600),
+        }
+    }
+
+    /// Gets the approximate pixel density of the physical screen of the model, in pixels per inch,
+    /// or `None` when the physical screen size isn't published.
+    ///
+    /// Values are derived from [`screen_size`](Self::screen_size) and the nominal screen diagonals
+    /// from Ledger specifications, rounded to the nearest integer. Speculos itself doesn't expose
+    /// any physical dimension, and touch coordinates are always in pixels.
+    ///
+    /// | Model       | Diagonal | Density |
+    /// |-------------|----------|---------|
+    /// | Nano S      | 0.91"    | 145     |
+    /// | Nano X      | -        | -       |
+    /// | Nano S Plus | -        | -       |
+    /// | Blue        | 3.5"     | 165     |
+    /// | Stax        | 3.7"     | 211     |
+    /// | Flex        | 2.84"    | 271     |
+    pub const fn screen_dpi(&self) -> Option<u32> {
+        match self {
+            Self::Nanos => Some(145),
+            Self::Nanox | Self::Nanosp => None,
+            Self::Blue => Some(165),
+            Self::Stax => Some(211),
+            Self::Flex => Some(271),
+        }
+    }
+
+    /// Checks whether the model is operated via a touchscreen, i.e. with [`SpeculosClient::touch`].
+    pub const fn has_touchscreen(&self) -> bool {
+        matches!(self, Self::Blue | Self::Stax | Self::Flex)
+    }
+
+    /// Checks whether the model is operated via buttons, i.e. with
+    /// [`SpeculosClient::press_button`].
+    pub const fn has_buttons(&self) -> bool {
+        matches!(self, Self::Nanos | Self::Nanox | Self::Nanosp)
+    }
+
+    /// Gets the screen area of a common touchscreen button, or `None` on models without a
+    /// touchscreen layout for it.
+    ///
+    /// Coordinates target the NBGL layouts of Stax and Flex firmware 1.x, and the BAGL approval
+    /// layout of the Blue SDK samples, which only has confirm and reject buttons. Apps with custom
+    /// layouts or other firmware versions may place buttons elsewhere.
+    pub const fn button_region(&self, button: NamedButton) -> Option<Rect> {
+        let (x, y, width, height) = match (self, button) {
+            (Self::Stax, NamedButton::Confirm) => (200, 560, 200, 96),
+            (Self::Stax, NamedButton::Reject) => (0, 560, 80, 96),
+            (Self::Stax, NamedButton::Back) => (0, 16, 80, 80),
+            (Self::Stax, NamedButton::Settings) => (304, 16, 80, 80),
+            (Self::Flex, NamedButton::Confirm) => (240, 490, 240, 80),
+            (Self::Flex, NamedButton::Reject) => (15, 490, 80, 80),
+            (Self::Flex, NamedButton::Back) => (15, 35, 80, 80),
+            (Self::Flex, NamedButton::Settings) => (365, 35, 80, 80),
+            (Self::Blue, NamedButton::Confirm) => (165, 414, 115, 36),
+            (Self::Blue, NamedButton::Reject) => (40, 414, 115, 36),
+            _ => return None,
+        };
+
+        Some(Rect {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+
+    /// Gets the screen area of the status bar showing the clock and battery level, or `None` on
+    /// models without one.
+    ///
+    /// Only Blue displays a status bar. Nano models have none, and Stax and Flex only show status
+    /// information on the dashboard, outside of apps.
+    pub const fn status_bar_region(&self) -> Option<Rect> {
+        match self {
+            Self::Blue => Some(Rect {
+                x: 0,
+                y: 0,
+                width: 320,
+                height: 20,
+            }),
+            _ => None,
+        }
+    }
+
+    const fn home_text(&self) -> &'static str {
+        match self {
+            Self::Nanos | Self::Nanox | Self::Nanosp | Self::Blue => "is ready",
+            Self::Stax | Self::Flex => "Quit app",
+        }
+    }
+
+    /// Gets the SDK versions Speculos can emulate for the model, for use with
+    /// [`SpeculosClientBuilder::sdk`].
+    ///
+    /// The list reflects the SDK versions bundled with Speculos releases up to 0.25 and may lag
+    /// behind newer releases, in which case `--sdk` can be passed via
+    /// [`SpeculosClientBuilder::arg`] instead. It's empty for models identified by API level only.
+    pub const fn supported_sdks(&self) -> &'static [&'static str] {
+        match self {
+            Self::Nanos => &["1.5", "1.6", "2.0", "2.1"],
+            Self::Nanox => &["1.2", "2.0", "2.0.2"],
+            Self::Nanosp => &["1.0", "1.0.3"],
+            Self::Blue => &["1.5", "2.2.5"],
+            Self::Stax | Self::Flex => &[],
+        }
+    }
+
+    const fn supports_sdk(&self) -> bool {
+        !self.supported_sdks().is_empty()
+    }
+
+    /// Gets the API levels Speculos can emulate for the model, for use with
+    /// [`SpeculosClientBuilder::api_level`].
+    ///
+    /// The range reflects the API levels bundled with Speculos releases up to 0.25 and may lag
+    /// behind newer releases, in which case `--apiLevel` can be passed via
+    /// [`SpeculosClientBuilder::arg`] instead. It's `None` for models identified by SDK version
+    /// only.
+    pub const fn supported_api_levels(&self) -> Option<RangeInclusive<u32>> {
+        match self {
+            Self::Nanox | Self::Nanosp | Self::Stax | Self::Flex => Some(1..=25),
+            Self::Nanos | Self::Blue => None,
+        }
+    }
+
+    const fn supports_api_level(&self) -> bool {
+        self.supported_api_levels().is_some()
+    }
+}
+
+impl FromStr for DeviceModel {
+    type Err = ParseDeviceModelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "nanos" => Self::Nanos,
+            "nanox" => Self::Nanox,
+            "nanosp" => Self::Nanosp,
+            "blue" => Self::Blue,
+            "stax" => Self::Stax,
+            "flex" => Self::Flex,
+            _ => return Err(ParseDeviceModelError(s.to_owned())),
+        })
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<ScreenEvent, SpeculosError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+impl std::fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream").finish_non_exhaustive()
+    }
+}
+
+impl EndpointMetrics {
+    /// Gets the mean request latency, or `None` if no request was made.
+    pub fn mean(&self) -> Option<Duration> {
+        match self.count {
+            0 => None,
+            count => Some(self.total.div_f64(count as f64)),
+        }
+    }
+
+    fn record(&mut self, elapsed: Duration) {
+        self.count += 1;
+        self.min = self.min.min(elapsed);
+        self.max = self.max.max(elapsed);
+        self.total += elapsed;
+    }
+}
+
+impl ApduFault {
+    fn response(self) -> Result<[u8; 2], SpeculosError> {
+        match self {
+            Self::StatusWord(sw) => Ok(sw.to_be_bytes()),
+            Self::Disconnect => {
+                Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset).into())
+            }
+        }
+    }
+}
+
+impl GoldenSequence {
+    /// Creates an empty sequence.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            screens: Vec::new(),
+        }
+    }
+
+    /// Appends a screen, unless its text is the same as the last screen's.
+    ///
+    /// Returns whether the screen was appended.
+    pub fn push(&mut self, screen: GoldenScreen) -> bool {
+        if self
+            .screens
+            .last()
+            .is_some_and(|last| last.texts == screen.texts)
+        {
+            return false;
+        }
+
+        self.screens.push(screen);
+        true
+    }
+}
+
+impl Rect {
+    /// Checks whether a point falls within the area.
+    ///
+    /// The left and top edges are inclusive while the right and bottom edges are exclusive, so
+    /// adjacent areas never overlap.
+    pub const fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.x && y >= self.y && x - self.x < self.width && y - self.y < self.height
+    }
+
+    /// Gets the center point of the area, e.g. for tapping with [`SpeculosClient::touch`].
+    pub const fn center(&self) -> (u32, u32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+}
+
+impl StatusWord {
+    /// `9000`: success.
+    pub const OK: Self = Self(0x9000);
+    /// `6985`: denied, typically by the user.
+    pub const DENIED: Self = Self(0x6985);
+    /// `6982`: security status not satisfied.
+    pub const SECURITY_STATUS_NOT_SATISFIED: Self = Self(0x6982);
+    /// `6a80`: invalid data.
+    pub const INVALID_DATA: Self = Self(0x6a80);
+    /// `6a86`: incorrect P1 or P2.
+    pub const INCORRECT_P1_P2: Self = Self(0x6a86);
+    /// `6b00`: wrong parameters.
+    pub const WRONG_PARAMETERS: Self = Self(0x6b00);
+    /// `6700`: wrong length.
+    pub const WRONG_LENGTH: Self = Self(0x6700);
+    /// `6d00`: instruction not supported.
+    pub const INS_NOT_SUPPORTED: Self = Self(0x6d00);
+    /// `6e00`: class not supported.
+    pub const CLA_NOT_SUPPORTED: Self = Self(0x6e00);
+    /// `5515`: device locked.
+    pub const DEVICE_LOCKED: Self = Self(0x5515);
+
+    /// Gets the category of the status word.
+    pub const fn category(&self) -> StatusWordCategory {
+        match self.0 {
+            0x9000 | 0x6100..=0x61ff => StatusWordCategory::Success,
+            0x6200..=0x63ff => StatusWordCategory::Warning,
+            _ => StatusWordCategory::Error,
+        }
+    }
+
+    /// Gets the name of the status word, if it's one of the constants defined on this type.
+    pub const fn name(&self) -> Option<&'static str> {
+        Some(match self.0 {
+            0x9000 => "success",
+            0x6985 => "denied",
+            0x6982 => "security status not satisfied",
+            0x6a80 => "invalid data",
+            0x6a86 => "incorrect p1/p2",
+            0x6b00 => "wrong parameters",
+            0x6700 => "wrong length",
+            0x6d00 => "instruction not supported",
+            0x6e00 => "class not supported",
+            0x5515 => "device locked",
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(feature = "signature")]
+impl Signature {
+    /// Parses a signature from response data, excluding the status word, following `layout`.
+    ///
+    /// Data not matching `layout` results in [`SpeculosError::MalformedSignature`].
+    pub fn parse(data: &[u8], layout: SignatureLayout) -> Result<Self, SpeculosError> {
+        let split = |data: &[u8]| -> Option<([u8; 32], [u8; 32])> {
+            let (r, s) = data.split_first_chunk::<32>()?;
+            Some((*r, s.try_into().ok()?))
+        };
+
+        let signature = match layout {
+            SignatureLayout::Vrs => data
+                .split_first()
+                .and_then(|(v, rs)| split(rs).map(|(r, s)| Self { r, s, v: Some(*v) })),
+            SignatureLayout::Rsv => data
+                .split_last()
+                .and_then(|(v, rs)| split(rs).map(|(r, s)| Self { r, s, v: Some(*v) })),
+            SignatureLayout::Rs => split(data).map(|(r, s)| Self { r, s, v: None }),
+            SignatureLayout::Der => parse_der_signature(data),
+        };
+
+        signature.ok_or_else(|| SpeculosError::MalformedSignature {
+            layout,
+            data: data.to_vec(),
+        })
+    }
+}
+
+impl ApduResponse {
+    /// Gets the name of the status word, if it's a commonly used one.
+    ///
+    /// See [`StatusWord::name`] for the names known.
+    pub fn sw_name(&self) -> Option<&'static str> {
+        self.status_word().name()
+    }
+
+    /// Gets the status word as a [`StatusWord`].
+    pub fn status_word(&self) -> StatusWord {
+        StatusWord(self.sw)
+    }
+
+    /// Checks that the status word is `expected`, returning the response data if so.
+    pub fn expect_sw(self, expected: u16) -> Result<Vec<u8>, SpeculosError> {
+        if self.sw == expected {
+            Ok(self.data)
+        } else {
+            Err(SpeculosError::UnexpectedStatusWord {
+                expected,
+                actual: self.sw,
+            })
+        }
+    }
+}
+
+impl ApduMatcher {
+    /// Creates a matcher accepting any response with a status word.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expects the status word to be `sw`.
+    pub fn sw(mut self, sw: u16) -> Self {
+        self.sw = Some(sw);
+        self
+    }
+
+    /// Expects the response data, excluding the status word, to be exactly `data`.
+    pub fn data(mut self, data: &[u8]) -> Self {
+        self.data = Some(data.to_vec());
+        self
+    }
+
+    /// Expects the response data, excluding the status word, to be `len` bytes long.
+    pub fn data_len(mut self, len: usize) -> Self {
+        self.data_len = Some(len);
+        self
+    }
+
+    /// Expects the response data to start with `prefix`.
+    pub fn data_starts_with(mut self, prefix: &[u8]) -> Self {
+        self.data_prefix = Some(prefix.to_vec());
+        self
+    }
+
+    /// Checks a raw response, including the status word, against all expectations.
+    ///
+    /// The error describes the first unsatisfied expectation along with the full response in hex.
+    pub fn check(&self, response: &[u8]) -> Result<(), SpeculosError> {
+        let Some((data, sw)) = response.split_last_chunk::<2>() else {
+            return Err(SpeculosError::ApduResponseTooShort(response.len()));
+        };
+        let sw = u16::from_be_bytes(*sw);
+
+        let reason = if let Some(expected) = self.sw
+            && expected != sw
+        {
+            format!(
+                "expected SW {}, got {}",
+                StatusWord(expected),
+                StatusWord(sw)
+            )
+        } else if let Some(expected) = &self.data
+            && expected != data
+        {
+            format!("expected data {}", hex::encode(expected))
+        } else if let Some(expected) = self.data_len
+            && expected != data.len()
+        {
+            format!("expected {} bytes of data, got {}", expected, data.len())
+        } else if let Some(prefix) = &self.data_prefix
+            && !data.starts_with(prefix)
+        {
+            format!("expected data starting with {}", hex::encode(prefix))
+        } else {
+            return Ok(());
+        };
+
+        Err(SpeculosError::ApduMismatch {
+            reason,
+            response: response.to_vec(),
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for ApduResponse {
+    type Error = SpeculosError;
+
+    fn try_from(mut value: Vec<u8>) -> Result<Self, Self::Error> {
+        if value.len() < 2 {
+            return Err(SpeculosError::ApduResponseTooShort(value.len()));
+        }
+
+        let sw = value.split_off(value.len() - 2);
+        Ok(Self {
+            data: value,
+            sw: u16::from_be_bytes([sw[0], sw[1]]),
+        })
+    }
+}
+
+impl Button {
+    /// Gets the numeric code of the button used by Speculos, e.g. in automation rules: `1` for
+    /// the left button and `2` for the right one.
+    pub const fn as_code(&self) -> u8 {
+        match self {
+            Self::Left => 1,
+            Self::Right => 2,
+        }
+    }
+
+    const fn slug(&self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Right => "right",
+        }
+    }
+}
+
+impl TryFrom<u8> for Button {
+    type Error = SpeculosError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Left),
+            2 => Ok(Self::Right),
+            code => Err(SpeculosError::InvalidButtonCode(code)),
+        }
+    }
+}
+
+impl<'a> ApprovalFlow<'a> {
+    /// Creates an [`ApprovalFlow`] for button-operated devices, where the right button moves to
+    /// the next screen and pressing both buttons confirms.
+    pub fn buttons<R, C>(review_text: R, confirm_text: C) -> Self
+    where
+        R: Into<Cow<'a, str>>,
+        C: Into<Cow<'a, str>>,
+    {
+        Self {
+            review_text: review_text.into(),
+            confirm_text: confirm_text.into(),
+            next: Gesture::Button(Button::Right),
+            confirm: Gesture::BothButtons,
+        }
+    }
+
+    /// Creates an [`ApprovalFlow`] for touchscreen devices, or `None` if `model` has no
+    /// [confirm button region](DeviceModel::button_region).
+    ///
+    /// Review screens are moved through by tapping the confirm button region, where the NBGL
+    /// layouts of Stax and Flex place the navigation to the next screen. The operation is confirmed
+    /// by holding the same region for 2 seconds, as required by their "Hold to sign" buttons. Blue
+    /// has no such hold, so a tap confirms instead.
+    pub fn touch<R, C>(model: DeviceModel, review_text: R, confirm_text: C) -> Option<Self>
+    where
+        R: Into<Cow<'a, str>>,
+        C: Into<Cow<'a, str>>,
+    {
+        let (x, y) = model.button_region(NamedButton::Confirm)?.center();
+        let confirm = match model {
+            DeviceModel::Blue => Gesture::Tap { x, y },
+            _ => Gesture::LongPress {
+                x,
+                y,
+                duration: HOLD_TO_CONFIRM_DURATION,
+            },
+        };
+
+        Some(Self {
+            review_text: review_text.into(),
+            confirm_text: confirm_text.into(),
+            next: Gesture::Tap { x, y },
+            confirm,
+        })
+    }
+}
+
+impl AutomationRule<'_> {
+    /// Checks that the rule is a valid combination of matchers.
+    ///
+    /// A rule must not set both `text` and `regexp`, and must set at least one of `text`,
+    /// `regexp`, `x` or `y`. Speculos silently ignores rules violating these constraints.
+    pub fn validate(&self) -> Result<(), SpeculosError> {
+        if self.text.is_some() && self.regexp.is_some() {
+            return Err(AutomationRuleError::TextAndRegexp.into());
+        }
+        if self.text.is_none() && self.regexp.is_none() && self.x.is_none() && self.y.is_none() {
+            return Err(AutomationRuleError::NoMatcher.into());
+        }
+
+        Ok(())
+    }
+}
+
+fn dry_run_response(request: &Request, apdu_response: &[u8]) -> Response {
+    let body = match request.url().path() {
+        "/apdu" => format!(r#"{{"data":"{}"}}"#, hex::encode(apdu_response)),
+        "/events" => r#"{"events":[]}"#.to_owned(),
+        _ => String::new(),
+    };
+    http::Response::new(body).into()
+}
+
+async fn write_tcp(stream: &mut TcpStream, data: &[u8]) -> Result<(), std::io::Error> {
+    // Requests are prefixed with the APDU length. Speculos only processes complete frames.
+    let length = u32::try_from(data.len())
+        .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+    let mut frame = Vec::with_capacity(4 + data.len());
+    frame.extend_from_slice(&length.to_be_bytes());
+    frame.extend_from_slice(data);
+
+    stream.write_all(&frame).await
+}
+
+async fn read_tcp(stream: &mut TcpStream) -> Result<Vec<u8>, std::io::Error> {
+    // Responses are prefixed with the data length, which excludes the status word. Frames may
+    // arrive in pieces, which `read_exact` reassembles.
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length).await?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_APDU_RESPONSE_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "apdu response length {} exceeds {}",
+                length, MAX_APDU_RESPONSE_LEN
+            ),
+        ));
+    }
+
+    let mut response = vec![0u8; length + 2];
+    stream.read_exact(&mut response).await?;
+
+    Ok(response)
+}
+
+/// Checks whether a kept connection was closed by the peer, without waiting.
+///
+/// Unexpected pending data also counts as closed, as the connection is then out of sync.
+fn is_closed(stream: &TcpStream) -> bool {
+    let mut buffer = [0u8; 1];
+    let mut buffer = tokio::io::ReadBuf::new(&mut buffer);
+    let mut context = Context::from_waker(std::task::Waker::noop());
+
+    !matches!(stream.poll_peek(&mut context, &mut buffer), Poll::Pending)
+}
+
+fn is_disconnect(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::BrokenPipe
+            | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::UnexpectedEof
+    )
+}
+
+/// Gets the `name:version` pair ending a launcher line, if any.
+fn line_identity(line: &str) -> Option<(String, Option<String>)> {
+    // The pair follows `app name & version` on Speculos versions printing it, possibly quoted and
+    // after other words like `from env:`. The default marker has nothing after.
+    let identity = line
+        .split_once("app name & version")?
+        .1
+        .split_whitespace()
+        .last()?
+        .trim_matches(|c| c == '"' || c == '\'');
+    identity
+        .contains(':')
+        .then(|| split_identity(identity))
+        .filter(|(name, _)| !name.is_empty())
+}
+
+/// Splits an app identity in `name:version` form, as passed via `SPECULOS_APPNAME`.
+fn split_identity(identity: &str) -> (String, Option<String>) {
+    match identity.split_once(':') {
+        Some((name, version)) => (
+            name.to_owned(),
+            Some(version.to_owned()).filter(|version| !version.is_empty()),
+        ),
+        None => (identity.to_owned(), None),
+    }
+}
+
+/// Reads the app name and version embedded in an app ELF, if any.
+fn app_identity(app: &Path) -> Option<(String, Option<String>)> {
+    let elf = std::fs::read(app).ok()?;
+    let name = elf_metadata(&elf, "app_name")?;
+
+    Some((
+        name.to_owned(),
+        elf_metadata(&elf, "app_version").map(str::to_owned),
+    ))
+}
+
+/// Reads the API level embedded in an app ELF, if any.
+fn app_api_level(app: &Path) -> Option<u32> {
+    let elf = std::fs::read(app).ok()?;
+
+    elf_metadata(&elf, "api_level")?
+        .parse()
+        .ok()
+        .filter(|level| *level > 0)
+}
+
+/// Gets a Ledger SDK metadata item from an app ELF, stored as a NUL-terminated string in a
+/// `ledger.<item>` section.
+fn elf_metadata<'a>(elf: &'a [u8], item: &str) -> Option<&'a str> {
+    let section = elf_section(elf, &format!("ledger.{}", item))
+        .or_else(|| elf_section(elf, &format!(".ledger.{}", item)))?;
+
+    Some(
+        std::str::from_utf8(section)
+            .ok()?
+            .trim_end_matches('\0')
+            .trim(),
+    )
+    .filter(|value| !value.is_empty())
+}
+
+/// Gets the content of a named section from a little-endian ELF file.
+fn elf_section<'a>(elf: &'a [u8], name: &str) -> Option<&'a [u8]> {
+    // Reads a little-endian integer of `size` bytes at `base + offset`
+    let read = |base: usize, offset: usize, size: usize| -> Option<usize> {
+        let start = base.checked_add(offset)?;
+        let bytes = elf.get(start..start.checked_add(size)?)?;
+        bytes
+            .iter()
+            .rev()
+            .fold(0u64, |value, byte| (value << 8) | u64::from(*byte))
+            .try_into()
+            .ok()
+    };
+
+    if elf.get(..4)? != b"\x7fELF" || *elf.get(5)? != 1 {
+        return None;
+    }
+
+    // Field offsets of the file header and section headers differ between ELF32 and ELF64
+    let (word, shoff, shentsize, shnum, shstrndx, sh_offset, sh_size) = match elf.get(4)? {
+        1 => (4, 0x20, 0x2e, 0x30, 0x32, 0x10, 0x14),
+        2 => (8, 0x28, 0x3a, 0x3c, 0x3e, 0x18, 0x20),
+        _ => return None,
+    };
+
+    let shoff = read(0, shoff, word)?;
+    let shentsize = read(0, shentsize, 2)?;
+    let shnum = read(0, shnum, 2)?;
+    // Truncated files are rejected even if the fields read happen to be present
+    elf.get(shoff..shoff.checked_add(shnum.checked_mul(shentsize)?)?)?;
+    let header = |index: usize| shoff.checked_add(index.checked_mul(shentsize)?);
+    let content = |header: usize| {
+        let offset = read(header, sh_offset, word)?;
+        elf.get(offset..offset.checked_add(read(header, sh_size, word)?)?)
+    };
+
+    let strtab = content(header(read(0, shstrndx, 2)?)?)?;
+
+    (0..shnum).find_map(|index| {
+        let header = header(index)?;
+        let section_name = strtab.get(read(header, 0, 4)?..)?;
+        let section_name = &section_name[..section_name.iter().position(|byte| *byte == 0)?];
+
+        if section_name == name.as_bytes() {
+            content(header)
+        } else {
+            None
+        }
+    })
+}
+
+/// Parses a BIP32 derivation path such as `m/44'/60'/0'/0/0`, with hardened components marked by
+/// `'` or `h`.
+#[cfg(feature = "coins-ledger")]
+fn parse_derivation_path(path: &str) -> Result<Vec<u32>, SpeculosError> {
+    let invalid = || SpeculosError::InvalidDerivationPath(path.to_owned());
+
+    let mut components = path.split('/');
+    if components.next() != Some("m") {
+        return Err(invalid());
+    }
+
+    let components = components
+        .map(|component| {
+            let (index, hardened) = match component.strip_suffix(['\'', 'h']) {
+                Some(index) => (index, true),
+                None => (component, false),
+            };
+            let index = index
+                .parse::<u32>()
+                .ok()
+                .filter(|index| *index < 0x8000_0000)?;
+
+            Some(if hardened { index | 0x8000_0000 } else { index })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)?;
+
+    // Ledger apps reject deeper paths
+    if components.len() > 10 {
+        return Err(invalid());
+    }
+
+    Ok(components)
+}
+
+fn probe_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(home) = std::env::var_os("HOME") {
+        paths.push(Path::new(&home).join(".local/bin/speculos"));
+    }
+    paths.push(PathBuf::from(".venv/bin/speculos"));
+    paths.push(PathBuf::from("/opt/speculos/bin/speculos"));
+    paths
+}
+
+fn serialize_actions<S>(actions: &[AutomationAction<'_>], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    // `BothButtons` expands into one element per button
+    let mut seq = serializer.serialize_seq(None)?;
+    for action in actions.iter() {
+        match action {
+            AutomationAction::BothButtons { pressed } => {
+                for button in [Button::Left, Button::Right] {
+                    seq.serialize_element(&AutomationAction::Button {
+                        button,
+                        pressed: *pressed,
+                    })?;
+                }
+            }
+            action => seq.serialize_element(action)?,
+        }
+    }
+    seq.end()
+}
+
+fn serialize_screenshot<S>(screenshot: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    screenshot.as_ref().map(hex::encode).serialize(serializer)
+}
+
+fn deserialize_screenshot<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(hex::decode)
+        .transpose()
+        .map_err(de::Error::custom)
+}
+
+/// Serializes automation rules into the request body sent by [`SpeculosClient::automation`].
+///
+/// Rules are validated the same way. The body uses the default schema version, unlike requests
+/// from clients configured with [`SpeculosClientBuilder::automation_version`].
+pub fn serialize_automation(rules: &[AutomationRule<'_>]) -> Result<String, SpeculosError> {
+    Ok(serde_json::to_string(&automation_request(
+        rules,
+        DEFAULT_AUTOMATION_VERSION,
+    )?)?)
+}
+
+fn automation_request<'a, 'b>(
+    rules: &'a [AutomationRule<'b>],
+    version: u32,
+) -> Result<PostAutomationRequest<'a, 'b>, SpeculosError> {
+    for rule in rules {
+        rule.validate()?;
+    }
+
+    Ok(PostAutomationRequest { version, rules })
+}
+
+/// Converts an unexpected process exit into an error, telling crashes apart on Unix.
+fn exit_error(status: ExitStatus, output: &Mutex<VecDeque<String>>) -> SpeculosError {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        // SIGILL, SIGABRT, SIGFPE and SIGSEGV share these numbers across Unix platforms
+        if let Some(signal) = status.signal()
+            && (matches!(signal, 4 | 6 | 8 | 11) || status.core_dumped())
+        {
+            let output = output.lock().unwrap_or_else(PoisonError::into_inner);
+            return SpeculosError::AppCrashed {
+                signal,
+                stderr: output
+                    .iter()
+                    .skip(output.len().saturating_sub(CRASH_OUTPUT_LINES))
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            };
+        }
+    }
+
+    SpeculosError::ProcessExited(status)
+}
+
+/// Joins text elements into rows, one line per row, in reading order.
+fn rows_text(mut events: Vec<ScreenEvent>) -> String {
+    events.sort_by_key(|event| event.y);
+
+    let mut rows: Vec<(u32, Vec<ScreenEvent>)> = Vec::new();
+    for event in events {
+        match rows.last_mut() {
+            Some((y, row)) if event.y - *y <= ROW_TOLERANCE => row.push(event),
+            _ => rows.push((event.y, vec![event])),
+        }
+    }
+
+    rows.into_iter()
+        .map(|(_, mut row)| {
+            row.sort_by_key(|event| event.x);
+            row.iter()
+                .map(|event| event.text.trim())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Trims each line of a text, along with blank lines at its start and end.
+fn trim_lines(text: &str) -> String {
+    text.trim()
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Diffs two texts line by line, prefixing each line with `-`, `+` or a space.
+fn line_diff(expected: &str, actual: &str) -> Vec<String> {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+
+    // Lengths of the longest common subsequences of all suffix pairs
+    let mut lcs = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            diff.push(format!("  {}", expected[i]));
+            i += 1;
+            j += 1;
+        } else if j == actual.len() || (i < expected.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push(format!("- {}", expected[i]));
+            i += 1;
+        } else {
+            diff.push(format!("+ {}", actual[j]));
+            j += 1;
+        }
+    }
+
+    diff
+}
+
+/// Quotes a word for POSIX shells, leaving it as is when no quoting is needed.
+fn shell_quote(word: &str) -> String {
+    let plain = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+
+    match plain {
+        true => word.to_owned(),
+        false => format!("'{}'", word.replace('\'', "'\\''")),
+    }
+}
+
+#[cfg(feature = "signature")]
+fn parse_der_signature(data: &[u8]) -> Option<Signature> {
+    let [0x30, length, body @ ..] = data else {
+        return None;
+    };
+    if usize::from(*length) != body.len() {
+        return None;
+    }
+
+    let (r, body) = der_integer(body)?;
+    let (s, body) = der_integer(body)?;
+    if !body.is_empty() {
+        return None;
+    }
+
+    Some(Signature { r, s, v: None })
+}
+
+/// Reads a positive DER `INTEGER` of up to 32 bytes, left-padded with zeros.
+#[cfg(feature = "signature")]
+fn der_integer(data: &[u8]) -> Option<([u8; 32], &[u8])> {
+    let [0x02, length, rest @ ..] = data else {
+        return None;
+    };
+    let length = usize::from(*length);
+    if length == 0 || length > rest.len() {
+        return None;
+    }
+
+    let (integer, rest) = rest.split_at(length);
+    // A leading zero byte only keeps the sign bit clear
+    let start = integer.iter().position(|byte| *byte != 0).unwrap_or(length);
+    let integer = &integer[start..];
+    if integer.len() > 32 {
+        return None;
+    }
+
+    let mut padded = [0u8; 32];
+    padded[32 - integer.len()..].copy_from_slice(integer);
+    Some((padded, rest))
+}
+
+/// Reads the dimensions of a PNG image from its header.
+fn png_size(png: &[u8]) -> Option<(u32, u32)> {
+    let header = png.get(..24)?;
+    if !header.starts_with(b"\x89PNG\r\n\x1a\n") || &header[12..16] != b"IHDR" {
+        return None;
+    }
+
+    Some((
+        u32::from_be_bytes(header[16..20].try_into().ok()?),
+        u32::from_be_bytes(header[20..24].try_into().ok()?),
+    ))
+}
+
+/// Checks whether the API answers a current screen request successfully, using a plain HTTP/1.1
+/// request so that no async runtime is needed.
+fn probe_api(host: &str, port: u16, timeout: Duration) -> bool {
+    use std::{
+        io::{Read, Write},
+        net::ToSocketAddrs,
+    };
+
+    let probe = || -> std::io::Result<bool> {
+        let mut stream = (host, port)
+            .to_socket_addrs()?
+            .find_map(|addr| std::net::TcpStream::connect_timeout(&addr, timeout).ok())
+            .ok_or(std::io::ErrorKind::ConnectionRefused)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+
+        // IPv6 addresses must be bracketed in the host header too
+        let authority = match host.contains(':') {
+            true => format!("[{}]:{}", host, port),
+            false => format!("{}:{}", host, port),
+        };
+        write!(
+            stream,
+            "GET /events?currentscreenonly=true HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            authority
+        )?;
+
+        // Only the status line matters, e.g. `HTTP/1.1 200 OK`
+        let mut status = [0u8; 12];
+        stream.read_exact(&mut status)?;
+        Ok(status.starts_with(b"HTTP/1.") && status[9] == b'2')
+    };
+
+    probe().unwrap_or(false)
+}
+
+fn screen_contains(events: &[ScreenEvent], text: &str) -> bool {
+    events.iter().any(|event| event.text.contains(text))
+}
+
+impl Serialize for PostApduRequest<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.field, &hex::encode(self.data))?;
+        map.end()
+    }
+}
+
+impl<'a> Serialize for AutomationCondition<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(2))?;
+        seq.serialize_element(&self.varname)?;
+        seq.serialize_element(&self.value)?;
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for AutomationCondition<'_> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (varname, value) = <(String, bool)>::deserialize(deserializer)?;
+
+        Ok(Self {
+            varname: Cow::Owned(varname),
+            value,
+        })
+    }
+}
 
 impl<'a> Serialize for AutomationAction<'a> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -269,13 +4888,14 @@ impl<'a> Serialize for AutomationAction<'a> {
             Self::Button { button, pressed } => {
                 let mut seq = serializer.serialize_seq(Some(3))?;
                 seq.serialize_element("button")?;
-                seq.serialize_element(&match button {
-                    Button::Left => 1,
-                    Button::Right => 2,
-                })?;
+                seq.serialize_element(&button.as_code())?;
                 seq.serialize_element(pressed)?;
                 seq.end()
             }
+            // Only expressible as part of an action list, where it's expanded
+            Self::BothButtons { .. } => Err(serde::ser::Error::custom(
+                "both buttons action can only be serialized within an automation rule",
+            )),
             Self::Finger { x, y, touched } => {
                 let mut seq = serializer.serialize_seq(Some(4))?;
                 seq.serialize_element("finger")?;
@@ -300,6 +4920,71 @@ impl<'a> Serialize for AutomationAction<'a> {
     }
 }
 
+impl<'de> Deserialize<'de> for AutomationAction<'_> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ActionVisitor;
+
+        impl<'de> Visitor<'de> for ActionVisitor {
+            type Value = AutomationAction<'static>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(formatter, "an automation action array")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let action: String = element(&mut seq, 0)?;
+                Ok(match action.as_str() {
+                    "button" => AutomationAction::Button {
+                        button: {
+                            let code: u8 = element(&mut seq, 1)?;
+                            Button::try_from(code).map_err(|_| {
+                                de::Error::invalid_value(
+                                    de::Unexpected::Unsigned(code.into()),
+                                    &"1 or 2",
+                                )
+                            })?
+                        },
+                        pressed: element(&mut seq, 2)?,
+                    },
+                    "finger" => AutomationAction::Finger {
+                        x: element(&mut seq, 1)?,
+                        y: element(&mut seq, 2)?,
+                        touched: element(&mut seq, 3)?,
+                    },
+                    "setbool" => AutomationAction::Setbool {
+                        varname: Cow::Owned(element(&mut seq, 1)?),
+                        value: element(&mut seq, 2)?,
+                    },
+                    "exit" => AutomationAction::Exit,
+                    action => {
+                        return Err(de::Error::unknown_variant(
+                            action,
+                            &["button", "finger", "setbool", "exit"],
+                        ));
+                    }
+                })
+            }
+        }
+
+        fn element<'de, A, T>(seq: &mut A, index: usize) -> Result<T, A::Error>
+        where
+            A: SeqAccess<'de>,
+            T: Deserialize<'de>,
+        {
+            seq.next_element()?
+                .ok_or_else(|| de::Error::invalid_length(index, &"an automation action array"))
+        }
+
+        deserializer.deserialize_seq(ActionVisitor)
+    }
+}
+
 impl From<std::io::Error> for SpeculosError {
     fn from(value: std::io::Error) -> Self {
         Self::IoError(value)
@@ -312,13 +4997,851 @@ impl From<reqwest::Error> for SpeculosError {
     }
 }
 
+impl From<serde_json::Error> for SpeculosError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::JsonError(value)
+    }
+}
+
+impl From<hex::FromHexError> for SpeculosError {
+    fn from(value: hex::FromHexError) -> Self {
+        Self::HexError(value)
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<image::ImageError> for SpeculosError {
+    fn from(value: image::ImageError) -> Self {
+        Self::ImageError(value)
+    }
+}
+
+#[cfg(feature = "coins-ledger")]
+impl From<coins_ledger::LedgerError> for SpeculosError {
+    fn from(value: coins_ledger::LedgerError) -> Self {
+        Self::LedgerError(value)
+    }
+}
+
+#[cfg(feature = "qr")]
+impl From<rqrr::DeQRError> for SpeculosError {
+    fn from(value: rqrr::DeQRError) -> Self {
+        Self::QrError(value)
+    }
+}
+
+impl From<AutomationRuleError> for SpeculosError {
+    fn from(value: AutomationRuleError) -> Self {
+        Self::InvalidAutomationRule(value)
+    }
+}
+
+impl Display for AutomationRuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TextAndRegexp => write!(f, "`text` and `regexp` cannot be both set"),
+            Self::NoMatcher => write!(f, "one of `text`, `regexp`, `x` or `y` must be set"),
+        }
+    }
+}
+
+impl Error for AutomationRuleError {}
+
+impl Display for StatusWord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{:04x} ({})", self.0, name),
+            None => write!(f, "{:04x}", self.0),
+        }
+    }
+}
+
+impl From<u16> for StatusWord {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<StatusWord> for u16 {
+    fn from(value: StatusWord) -> Self {
+        value.0
+    }
+}
+
+impl Display for ParseDeviceModelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown device model: {}", self.0)
+    }
+}
+
+impl Error for ParseDeviceModelError {}
+
 impl Display for SpeculosError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::IoError(error) => write!(f, "{}", error),
             Self::ReqwestError(error) => write!(f, "{}", error),
+            Self::InvalidAutomationRule(error) => write!(f, "invalid automation rule: {}", error),
+            Self::Timeout => write!(f, "operation timed out"),
+            Self::ProcessExited(status) => write!(f, "speculos process exited: {}", status),
+            Self::AppCrashed { signal, stderr } if stderr.is_empty() => {
+                write!(f, "speculos crashed with signal {}", signal)
+            }
+            Self::AppCrashed { signal, stderr } => {
+                write!(f, "speculos crashed with signal {}:\n{}", signal, stderr)
+            }
+            Self::StartupFailed { output } => write!(f, "speculos failed to start:\n{}", output),
+            Self::AppNotFound(path) => write!(f, "app not found: {}", path.display()),
+            Self::EmptyAppPath => write!(f, "app path is empty"),
+            Self::AppNotAFile(path) => write!(f, "app is not a file: {}", path.display()),
+            Self::ApduPortDisabled => write!(f, "apdu port not enabled"),
+            Self::InvalidLaunchOption(reason) => write!(f, "invalid launch option: {}", reason),
+            Self::MissingEnvVar(name) => write!(f, "environment variable {} not set", name),
+            Self::InvalidEnvVar { name, value } => {
+                write!(
+                    f,
+                    "invalid value for environment variable {}: {}",
+                    name, value
+                )
+            }
+            Self::JsonError(error) => write!(f, "{}", error),
+            Self::HexError(error) => write!(f, "{}", error),
+            #[cfg(feature = "image")]
+            Self::ImageError(error) => write!(f, "{}", error),
+            #[cfg(feature = "coins-ledger")]
+            Self::LedgerError(error) => write!(f, "{}", error),
+            #[cfg(feature = "qr")]
+            Self::QrCodeNotFound => write!(f, "no QR code found on screen"),
+            #[cfg(feature = "qr")]
+            Self::QrError(error) => write!(f, "{}", error),
+            Self::Unresponsive => write!(f, "speculos stopped responding"),
+            Self::PoolExhausted => write!(f, "no speculos instance left in pool"),
+            Self::SpeculosNotFound { tried } => {
+                write!(f, "speculos not found, tried: ")?;
+                for (index, path) in tried.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", path.display())?;
+                }
+                Ok(())
+            }
+            Self::ApduResponseTooShort(length) => {
+                write!(f, "apdu response too short: {} bytes", length)
+            }
+            Self::UnexpectedStatusWord { expected, actual } => write!(
+                f,
+                "expected SW {}, got {}",
+                StatusWord(*expected),
+                StatusWord(*actual)
+            ),
+            Self::ApduMismatch { reason, response } => {
+                write!(f, "{}, response: {}", reason, hex::encode(response))
+            }
+            Self::ScreenMismatch {
+                index,
+                expected,
+                actual,
+            } if expected == actual => write!(f, "screenshot mismatch at screen {}", index),
+            Self::ScreenMismatch {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "screen mismatch at screen {}: expected {:?}, got {:?}",
+                index, expected, actual
+            ),
+            Self::ScreenTextMismatch { expected, actual } => {
+                write!(f, "screen text mismatch (-expected +actual):")?;
+                for line in line_diff(expected, actual) {
+                    write!(f, "\n{}", line)?;
+                }
+                Ok(())
+            }
+            Self::ApduReconnectFailed(error) => {
+                write!(f, "failed to reconnect to apdu port: {}", error)
+            }
+            #[cfg(feature = "coins-ledger")]
+            Self::InvalidDerivationPath(path) => write!(f, "invalid derivation path: {}", path),
+            #[cfg(feature = "coins-ledger")]
+            Self::MalformedAddressResponse(data) => {
+                write!(f, "malformed get-address response: {}", hex::encode(data))
+            }
+            #[cfg(feature = "coins-ledger")]
+            Self::AddressMismatch { expected, actual } => {
+                let index = expected
+                    .chars()
+                    .zip(actual.chars())
+                    .take_while(|(expected, actual)| expected == actual)
+                    .count();
+                write!(
+                    f,
+                    "address mismatch at character {}:\n  expected: {}\n  actual:   {}\n            {}^",
+                    index,
+                    expected,
+                    actual,
+                    " ".repeat(index)
+                )
+            }
+            Self::TouchOutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            } => write!(
+                f,
+                "touch at ({}, {}) outside of {}x{} screen",
+                x, y, width, height
+            ),
+            Self::ForbiddenText { text } => write!(f, "forbidden text displayed: {:?}", text),
+            Self::PortInUse(port) => write!(
+                f,
+                "port {} already in use, each client needs distinct ports",
+                port
+            ),
+            Self::AutomationRejected { reason } => {
+                write!(f, "automation rejected by speculos: {}", reason)
+            }
+            Self::InvalidButtonCode(code) => write!(f, "invalid button code: {}", code),
+            Self::MalformedScreenshot => write!(f, "screenshot is not a valid png image"),
+            Self::ModelMismatch {
+                expected,
+                width,
+                height,
+            } => {
+                let (expected_width, expected_height) = expected.screen_size();
+                write!(
+                    f,
+                    "model mismatch: expected {} with a {}x{} screen, got a {}x{} screen",
+                    expected.slug(),
+                    expected_width,
+                    expected_height,
+                    width,
+                    height
+                )
+            }
+            #[cfg(feature = "gif")]
+            Self::ZeroInterval => write!(f, "capture interval must not be zero"),
+            #[cfg(feature = "signature")]
+            Self::MalformedSignature { layout, data } => {
+                write!(f, "malformed {:?} signature: {}", layout, hex::encode(data))
+            }
+        }
+    }
+}
+
+impl Error for SpeculosError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            // Variants displaying the wrapped error as-is skip it to avoid repeating its message
+            Self::IoError(error) => error.source(),
+            Self::ReqwestError(error) => error.source(),
+            Self::JsonError(error) => error.source(),
+            #[cfg(feature = "image")]
+            Self::ImageError(error) => error.source(),
+            #[cfg(feature = "coins-ledger")]
+            Self::LedgerError(error) => error.source(),
+            #[cfg(feature = "qr")]
+            Self::QrError(error) => error.source(),
+            Self::InvalidAutomationRule(error) => Some(error),
+            Self::ApduReconnectFailed(error) => Some(error),
+            _ => None,
         }
     }
 }
 
-impl Error for SpeculosError {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_buttons_action_does_not_serialize_standalone() {
+        let action = AutomationAction::BothButtons { pressed: true };
+
+        assert!(serde_json::to_value(&action).is_err());
+    }
+
+    #[test]
+    fn automation_rule_round_trip() {
+        let rule = AutomationRule {
+            text: Some("Approve".into()),
+            regexp: None,
+            x: Some(10),
+            y: None,
+            conditions: vec![AutomationCondition {
+                varname: "seen".into(),
+                value: false,
+            }]
+            .into(),
+            actions: vec![
+                AutomationAction::Button {
+                    button: Button::Right,
+                    pressed: true,
+                },
+                AutomationAction::BothButtons { pressed: false },
+                AutomationAction::Finger {
+                    x: 1,
+                    y: 2,
+                    touched: true,
+                },
+                AutomationAction::Setbool {
+                    varname: "seen".into(),
+                    value: true,
+                },
+                AutomationAction::Exit,
+            ]
+            .into(),
+        };
+
+        let json = serde_json::to_string(&rule).unwrap();
+        let decoded: AutomationRule = serde_json::from_str(&json).unwrap();
+
+        // `BothButtons` comes back as its two button actions
+        let mut expected = rule.clone();
+        expected.actions = vec![
+            AutomationAction::Button {
+                button: Button::Right,
+                pressed: true,
+            },
+            AutomationAction::Button {
+                button: Button::Left,
+                pressed: false,
+            },
+            AutomationAction::Button {
+                button: Button::Right,
+                pressed: false,
+            },
+            AutomationAction::Finger {
+                x: 1,
+                y: 2,
+                touched: true,
+            },
+            AutomationAction::Setbool {
+                varname: "seen".into(),
+                value: true,
+            },
+            AutomationAction::Exit,
+        ]
+        .into();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn automation_rule_rejects_invalid_actions() {
+        let bad_button = r#"{"text":"a","actions":[["button",3,true]]}"#;
+        let unknown_action = r#"{"text":"a","actions":[["jump"]]}"#;
+
+        assert!(serde_json::from_str::<AutomationRule>(bad_button).is_err());
+        assert!(serde_json::from_str::<AutomationRule>(unknown_action).is_err());
+    }
+
+    fn approve_rule(actions: Vec<AutomationAction<'static>>) -> AutomationRule<'static> {
+        AutomationRule {
+            text: Some("Approve".into()),
+            regexp: None,
+            x: None,
+            y: None,
+            conditions: Cow::Borrowed(&[]),
+            actions: actions.into(),
+        }
+    }
+
+    #[test]
+    fn serialize_automation_golden() {
+        let cases = [
+            (
+                AutomationAction::Button {
+                    button: Button::Left,
+                    pressed: true,
+                },
+                r#"[["button",1,true]]"#,
+            ),
+            (
+                AutomationAction::BothButtons { pressed: false },
+                r#"[["button",1,false],["button",2,false]]"#,
+            ),
+            (
+                AutomationAction::Finger {
+                    x: 100,
+                    y: 200,
+                    touched: true,
+                },
+                r#"[["finger",100,200,true]]"#,
+            ),
+            (
+                AutomationAction::Setbool {
+                    varname: "seen".into(),
+                    value: false,
+                },
+                r#"[["setbool","seen",false]]"#,
+            ),
+            (AutomationAction::Exit, r#"[["exit"]]"#),
+        ];
+
+        for (action, actions) in cases {
+            assert_eq!(
+                serialize_automation(&[approve_rule(vec![action])]).unwrap(),
+                format!(
+                    r#"{{"version":1,"rules":[{{"text":"Approve","conditions":[],"actions":{}}}]}}"#,
+                    actions
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn serialize_automation_conditions_and_matchers() {
+        let rule = AutomationRule {
+            text: None,
+            regexp: Some("^Amount".into()),
+            x: Some(0),
+            y: Some(20),
+            conditions: vec![AutomationCondition {
+                varname: "seen".into(),
+                value: true,
+            }]
+            .into(),
+            actions: vec![AutomationAction::Exit].into(),
+        };
+
+        assert_eq!(
+            serialize_automation(&[rule]).unwrap(),
+            r#"{"version":1,"rules":[{"regexp":"^Amount","x":0,"y":20,"conditions":[["seen",true]],"actions":[["exit"]]}]}"#
+        );
+        assert_eq!(
+            serialize_automation(&[]).unwrap(),
+            r#"{"version":1,"rules":[]}"#
+        );
+    }
+
+    #[test]
+    fn serialize_automation_validates_rules() {
+        let mut rule = approve_rule(vec![AutomationAction::Exit]);
+        rule.text = None;
+
+        assert!(matches!(
+            serialize_automation(&[rule]),
+            Err(SpeculosError::InvalidAutomationRule(
+                AutomationRuleError::NoMatcher
+            ))
+        ));
+    }
+
+    /// Builds a little-endian ELF file with the given sections, besides the null section and the
+    /// section name table.
+    fn build_elf(elf64: bool, sections: &[(&str, &[u8])]) -> Vec<u8> {
+        let put = |elf: &mut Vec<u8>, offset: usize, value: usize, size: usize| {
+            elf[offset..offset + size].copy_from_slice(&value.to_le_bytes()[..size]);
+        };
+        let (header_size, entry_size, word) = if elf64 { (64, 64, 8) } else { (52, 40, 4) };
+
+        let mut elf = vec![0u8; header_size];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[4] = if elf64 { 2 } else { 1 };
+        elf[5] = 1;
+
+        let mut names = vec![0u8];
+        let mut entries = vec![(0, 0, 0)];
+        let mut contents = Vec::new();
+        for (name, content) in [(".shstrtab", &[][..])].iter().chain(sections) {
+            entries.push((names.len(), 0, content.len()));
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+            contents.push(*content);
+        }
+        contents[0] = &names;
+        entries[1].2 = names.len();
+
+        for (index, content) in contents.iter().enumerate() {
+            entries[index + 1].1 = elf.len();
+            elf.extend_from_slice(content);
+        }
+
+        let shoff = elf.len();
+        for (name, offset, size) in &entries {
+            let header = elf.len();
+            elf.resize(header + entry_size, 0);
+            put(&mut elf, header, *name, 4);
+            put(
+                &mut elf,
+                header + if elf64 { 0x18 } else { 0x10 },
+                *offset,
+                word,
+            );
+            put(
+                &mut elf,
+                header + if elf64 { 0x20 } else { 0x14 },
+                *size,
+                word,
+            );
+        }
+
+        let (shoff_at, fields_at) = if elf64 { (0x28, 0x3a) } else { (0x20, 0x2e) };
+        put(&mut elf, shoff_at, shoff, word);
+        put(&mut elf, fields_at, entry_size, 2);
+        put(&mut elf, fields_at + 2, entries.len(), 2);
+        put(&mut elf, fields_at + 4, 1, 2);
+        elf
+    }
+
+    #[test]
+    fn elf_section_finds_api_level() {
+        for elf64 in [false, true] {
+            let elf = build_elf(elf64, &[(".text", b"code"), ("ledger.api_level", b"22\0")]);
+
+            assert_eq!(elf_section(&elf, "ledger.api_level"), Some(&b"22\0"[..]));
+            assert_eq!(elf_section(&elf, ".text"), Some(&b"code"[..]));
+            assert_eq!(elf_section(&elf, ".ledger.api_level"), None);
+        }
+    }
+
+    #[test]
+    fn elf_section_missing() {
+        for elf64 in [false, true] {
+            let elf = build_elf(elf64, &[(".text", b"code")]);
+
+            assert_eq!(elf_section(&elf, "ledger.api_level"), None);
+        }
+    }
+
+    #[test]
+    fn elf_section_rejects_truncated_and_garbage_input() {
+        let elf = build_elf(true, &[("ledger.api_level", b"22\0")]);
+        for length in 0..elf.len() {
+            assert_eq!(elf_section(&elf[..length], "ledger.api_level"), None);
+        }
+
+        let mut garbage = b"\x7fELF\x02\x01".to_vec();
+        garbage.resize(128, 0xff);
+        assert_eq!(elf_section(&garbage, "ledger.api_level"), None);
+        assert_eq!(elf_section(b"not an elf file", "ledger.api_level"), None);
+    }
+
+    #[test]
+    fn elf_section_rejects_big_endian() {
+        let mut elf = build_elf(false, &[("ledger.api_level", b"22\0")]);
+        elf[5] = 2;
+
+        assert_eq!(elf_section(&elf, "ledger.api_level"), None);
+    }
+
+    #[test]
+    fn app_api_level_parses_section() {
+        let path = std::env::temp_dir().join(format!("speculos-client-{}.elf", std::process::id()));
+        std::fs::write(&path, build_elf(false, &[(".ledger.api_level", b"13\0")])).unwrap();
+        let level = app_api_level(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(level, Some(13));
+    }
+
+    #[test]
+    fn launcher_info_from_line() {
+        let builder = SpeculosClient::builder(DeviceModel::Nanosp, 5000, "/apps/missing.elf");
+        let info = |line: &str| {
+            let info = builder.launcher_info(Some(line.to_owned()));
+            (info.app_name, info.app_version)
+        };
+
+        assert_eq!(
+            info("[*] launcher: using default app name & version"),
+            (None, None)
+        );
+        assert_eq!(
+            info("[*] launcher: using app name & version from env: Boilerplate:2.1.0"),
+            (Some("Boilerplate".to_owned()), Some("2.1.0".to_owned()))
+        );
+        assert_eq!(
+            info("launcher: app name & version 'Boilerplate:2.1.0'"),
+            (Some("Boilerplate".to_owned()), Some("2.1.0".to_owned()))
+        );
+        assert_eq!(
+            info("[*] launcher: using app name & version from elf: Boilerplate:"),
+            (Some("Boilerplate".to_owned()), None)
+        );
+        assert_eq!(
+            builder
+                .launcher_info(Some(
+                    "[*] launcher: using default app name & version".to_owned()
+                ))
+                .line
+                .as_deref(),
+            Some("[*] launcher: using default app name & version")
+        );
+    }
+
+    #[test]
+    fn launcher_info_from_env_and_elf() {
+        let path = std::env::temp_dir().join(format!(
+            "speculos-client-identity-{}.elf",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            build_elf(
+                true,
+                &[
+                    ("ledger.app_name", b"Boilerplate\0"),
+                    ("ledger.app_version", b"2.1.0\0"),
+                ],
+            ),
+        )
+        .unwrap();
+        let builder = SpeculosClient::builder(DeviceModel::Nanosp, 5000, &path);
+        let from_elf = builder.launcher_info(None);
+        let from_env = builder.clone().app_name("My App:1.0").launcher_info(None);
+        let without_version = builder.app_name("My App").launcher_info(None);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            (from_elf.line, from_elf.app_name, from_elf.app_version),
+            (
+                None,
+                Some("Boilerplate".to_owned()),
+                Some("2.1.0".to_owned())
+            )
+        );
+        assert_eq!(
+            (from_env.app_name, from_env.app_version),
+            (Some("My App".to_owned()), Some("1.0".to_owned()))
+        );
+        assert_eq!(
+            (without_version.app_name, without_version.app_version),
+            (Some("My App".to_owned()), None)
+        );
+    }
+
+    #[test]
+    fn rows_text_groups_within_tolerance() {
+        let event = |text: &str, x, y| ScreenEvent {
+            text: text.to_owned(),
+            x,
+            y,
+        };
+
+        assert_eq!(
+            rows_text(vec![
+                event("Review", 40, 3),
+                event("Amount", 0, 20),
+                event("1.5 ETH ", 60, 18),
+                event("transaction", 50, 5),
+                event("Fees", 0, 40),
+            ]),
+            "Review transaction\nAmount 1.5 ETH\nFees"
+        );
+        assert_eq!(
+            rows_text(vec![event("A", 0, 10), event("B", 0, 13)]),
+            "A\nB"
+        );
+        assert_eq!(rows_text(Vec::new()), "");
+    }
+
+    #[test]
+    fn touch_approval_flow() {
+        let flow = ApprovalFlow::touch(DeviceModel::Stax, "Review", "Hold to sign").unwrap();
+        assert_eq!(flow.next, Gesture::Tap { x: 300, y: 608 });
+        assert_eq!(
+            flow.confirm,
+            Gesture::LongPress {
+                x: 300,
+                y: 608,
+                duration: HOLD_TO_CONFIRM_DURATION
+            }
+        );
+
+        let flow = ApprovalFlow::touch(DeviceModel::Blue, "Review", "Confirm").unwrap();
+        assert_eq!(flow.confirm, Gesture::Tap { x: 222, y: 432 });
+
+        assert!(ApprovalFlow::touch(DeviceModel::Nanosp, "Review", "Approve").is_none());
+    }
+
+    #[test]
+    fn endpoint_metrics_mean() {
+        let mut metrics = EndpointMetrics {
+            count: 0,
+            min: Duration::MAX,
+            max: Duration::ZERO,
+            total: Duration::ZERO,
+        };
+        assert_eq!(metrics.mean(), None);
+
+        metrics.record(Duration::from_millis(10));
+        metrics.record(Duration::from_millis(20));
+        assert_eq!(metrics.mean(), Some(Duration::from_millis(15)));
+    }
+
+    #[test]
+    fn line_diff_cases() {
+        assert_eq!(line_diff("a\nb", "a\nb"), ["  a", "  b"]);
+        assert_eq!(line_diff("a\nc", "a\nb\nc"), ["  a", "+ b", "  c"]);
+        assert_eq!(line_diff("a\nb\nc", "a\nc"), ["  a", "- b", "  c"]);
+        assert_eq!(
+            line_diff("a\nb\nc", "a\nx\nc"),
+            ["  a", "- b", "+ x", "  c"]
+        );
+        assert_eq!(line_diff("", "a"), ["+ a"]);
+        assert_eq!(line_diff("a", ""), ["- a"]);
+    }
+
+    #[test]
+    fn trim_lines_handles_indented_literals() {
+        let expected = "
+            Review
+            transaction
+        ";
+
+        assert_eq!(trim_lines(expected), "Review\ntransaction");
+        assert_eq!(trim_lines("  Amount  \n\n 1.0 "), "Amount\n\n1.0");
+        assert_eq!(trim_lines(""), "");
+    }
+
+    #[test]
+    fn shell_quote_cases() {
+        assert_eq!(shell_quote("--api-port"), "--api-port");
+        assert_eq!(shell_quote("/apps/app.elf"), "/apps/app.elf");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote("$(rm -rf /)"), "'$(rm -rf /)'");
+    }
+
+    #[test]
+    fn launch_command_string_redacts_seed() {
+        let builder = SpeculosClient::builder(DeviceModel::Nanosp, 5000, "/apps/app.elf")
+            .seed("correct horse battery staple")
+            .app_name("My App:1.0")
+            .arg("it's");
+
+        let command = builder.launch_command_string().unwrap();
+
+        assert_eq!(
+            command,
+            r"'SPECULOS_APPNAME=My App:1.0' speculos --api-port 5000 --apdu-port 0 -m nanosp --display headless --seed '<redacted>' 'it'\''s' /apps/app.elf"
+        );
+        assert!(!command.contains("horse"));
+    }
+
+    #[test]
+    fn launch_validates_api_level() {
+        let builder = |model| SpeculosClient::builder(model, 5000, "/apps/app.elf");
+
+        assert!(builder(DeviceModel::Stax).api_level(22).validate().is_ok());
+        for api_level in [0, 26] {
+            assert!(matches!(
+                builder(DeviceModel::Stax).api_level(api_level).validate(),
+                Err(SpeculosError::InvalidLaunchOption(message)) if message.contains("expected 1 to 25")
+            ));
+        }
+        assert!(matches!(
+            builder(DeviceModel::Nanos).api_level(1).validate(),
+            Err(SpeculosError::InvalidLaunchOption(message)) if message.contains("use `sdk` instead")
+        ));
+    }
+
+    #[cfg(feature = "signature")]
+    fn rs() -> Vec<u8> {
+        let mut rs = vec![0x11; 32];
+        rs.extend([0x22; 32]);
+        rs
+    }
+
+    #[cfg(feature = "signature")]
+    #[test]
+    fn signature_raw_layouts() {
+        let expected = |v| Signature {
+            r: [0x11; 32],
+            s: [0x22; 32],
+            v,
+        };
+
+        let vrs = [&[0x1b][..], &rs()].concat();
+        let rsv = [&rs()[..], &[0x01]].concat();
+        assert_eq!(
+            Signature::parse(&vrs, SignatureLayout::Vrs).unwrap(),
+            expected(Some(0x1b))
+        );
+        assert_eq!(
+            Signature::parse(&rsv, SignatureLayout::Rsv).unwrap(),
+            expected(Some(0x01))
+        );
+        assert_eq!(
+            Signature::parse(&rs(), SignatureLayout::Rs).unwrap(),
+            expected(None)
+        );
+    }
+
+    #[cfg(feature = "signature")]
+    #[test]
+    fn signature_raw_layouts_reject_wrong_lengths() {
+        let rs = rs();
+        for (data, layout) in [
+            (&rs[..], SignatureLayout::Vrs),
+            (&rs[..], SignatureLayout::Rsv),
+            (&rs[1..], SignatureLayout::Rs),
+            (&[rs.as_slice(), &[0]].concat()[..], SignatureLayout::Rs),
+            (&[], SignatureLayout::Vrs),
+        ] {
+            assert!(matches!(
+                Signature::parse(data, layout),
+                Err(SpeculosError::MalformedSignature { .. })
+            ));
+        }
+    }
+
+    #[cfg(feature = "signature")]
+    #[test]
+    fn signature_der() {
+        // `r` has its high bit set and thus a leading zero, while `s` is short
+        let mut der = vec![0x30, 0x27, 0x02, 0x21, 0x00];
+        der.extend([0x80; 32]);
+        der.extend([0x02, 0x02, 0x01, 0x02]);
+
+        let signature = Signature::parse(&der, SignatureLayout::Der).unwrap();
+
+        let mut s = [0u8; 32];
+        s[30..].copy_from_slice(&[0x01, 0x02]);
+        assert_eq!(
+            signature,
+            Signature {
+                r: [0x80; 32],
+                s,
+                v: None
+            }
+        );
+    }
+
+    #[cfg(feature = "signature")]
+    #[test]
+    fn signature_der_rejects_malformed_input() {
+        let valid = [0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02];
+        assert!(Signature::parse(&valid, SignatureLayout::Der).is_ok());
+
+        let mut long_integer = vec![0x30, 0x26, 0x02, 0x21, 0x01];
+        long_integer.extend([0x80; 32]);
+        long_integer.extend([0x02, 0x01, 0x01]);
+
+        for data in [
+            // Trailing sighash type
+            &[&valid[..], &[0x01]].concat()[..],
+            // Sequence length off by one
+            &[0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02],
+            // Integer length past the end
+            &[0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x02, 0x02],
+            // Empty integer
+            &[0x30, 0x05, 0x02, 0x00, 0x02, 0x01, 0x02],
+            // Wrong tags
+            &[0x31, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02],
+            &[0x30, 0x06, 0x03, 0x01, 0x01, 0x02, 0x01, 0x02],
+            // Over 32 bytes without the leading zero
+            &long_integer,
+            &[],
+        ] {
+            assert!(
+                Signature::parse(data, SignatureLayout::Der).is_err(),
+                "{}",
+                hex::encode(data)
+            );
+        }
+    }
+}